@@ -1,3 +1,4 @@
+use crate::error::SourceMap;
 use crate::lexer::{LexerError, Loc, Token};
 use crate::parser::ParserError;
 
@@ -22,7 +23,10 @@ impl From<ParserError> for Error {
 use std::fmt;
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "parser error")
+        match self {
+            Error::Lexer(_) => write!(f, "lexer error"),
+            Error::Parser(_) => write!(f, "parser error"),
+        }
     }
 }
 
@@ -39,31 +43,31 @@ impl StdError for Error {
     }
 }
 
-pub fn print_annot(input: &str, loc: Loc) {
-    eprintln!("{}", input);
-    eprintln!("{}{}", " ".repeat(loc.0), "^".repeat(loc.1 - loc.0));
+pub fn print_annot(src: &SourceMap, loc: Loc) {
+    eprint!("{}", src.annotate(&loc));
 }
 
 impl Error {
-    pub fn show_diagnostic(&self, input: &str) {
+    pub fn show_diagnostic(&self, src: &SourceMap) {
         use self::Error::*;
         use self::ParserError as P;
-        let (e, loc): (&StdError, Loc) = match self {
+        let (e, loc): (&dyn StdError, Loc) = match self {
             Lexer(e) => (e, e.loc.clone()),
             Parser(e) => {
                 let loc = match e {
                     P::UnexpectedToken(Token { loc, .. })
                     | P::NotExpression(Token { loc, .. })
-                    | P::NotOperator(Token { loc, .. })
+                    | P::NotType(Token { loc, .. })
+                    | P::NotPattern(Token { loc, .. })
                     | P::UnclosedOpenParen(Token { loc, .. }) => loc.clone(),
-                    P::RedundantExpression(Token { loc, .. }) => Loc(loc.0, input.len()),
-                    P::Eof => Loc(input.len(), input.len() + 1),
+                    P::RedundantExpression(Token { loc, .. }) => Loc(loc.0, src.line_end(loc.0)),
+                    P::Eof => Loc(src.eof(), src.eof() + 1),
                 };
                 (e, loc)
             }
         };
         eprintln!("{}", e);
-        print_annot(input, loc);
+        print_annot(src, loc);
     }
 }
 
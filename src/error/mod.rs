@@ -1,3 +1,6 @@
+#[allow(clippy::module_inception)]
 mod error;
+mod source_map;
 
-pub use error::*;
\ No newline at end of file
+pub use error::*;
+pub use source_map::*;
\ No newline at end of file
@@ -0,0 +1,165 @@
+use crate::lexer::Loc;
+use std::cmp::{max, min};
+use std::fmt::Write;
+
+// spans covering more lines than this are printed with the middle elided
+const MAX_SPAN_LINES: usize = 4;
+
+/// Maps the byte offsets in a `Loc` back to lines and columns of the source.
+pub struct SourceMap<'a> {
+    name: &'a str,
+    src: &'a str,
+    // byte offset of the first char of every line
+    line_starts: Vec<usize>,
+}
+
+impl<'a> SourceMap<'a> {
+    pub fn new(name: &'a str, src: &'a str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(src.match_indices('\n').map(|(i, _)| i + 1));
+        SourceMap {
+            name,
+            src,
+            line_starts,
+        }
+    }
+
+    pub fn src(&self) -> &'a str {
+        self.src
+    }
+
+    // 0-origin index of the line containing `pos`
+    fn line_index(&self, pos: usize) -> usize {
+        match self.line_starts.binary_search(&pos) {
+            Ok(i) => i,
+            Err(i) => i - 1,
+        }
+    }
+
+    // contents of the line without its line break
+    fn line(&self, i: usize) -> &'a str {
+        let start = self.line_starts[i];
+        let end = match self.line_starts.get(i + 1) {
+            Some(next) => next - 1,
+            None => self.src.len(),
+        };
+        self.src[start..end].trim_end_matches('\r')
+    }
+
+    // number of chars in `src[start..end]`, tolerating offsets inside a char
+    fn width(&self, start: usize, end: usize) -> usize {
+        let end = min(end, self.src.len());
+        let start = min(start, end);
+        self.src.as_bytes()[start..end]
+            .iter()
+            .filter(|&&b| b & 0xc0 != 0x80)
+            .count()
+    }
+
+    /// 1-origin line and column of a byte offset. Columns count chars, not bytes.
+    pub fn line_col(&self, pos: usize) -> (usize, usize) {
+        let i = self.line_index(pos);
+        (i + 1, self.width(self.line_starts[i], pos) + 1)
+    }
+
+    /// Byte offset of the end of the line containing `pos`.
+    pub fn line_end(&self, pos: usize) -> usize {
+        let i = self.line_index(pos);
+        self.line_starts[i] + self.line(i).len()
+    }
+
+    /// Byte offset just after the last non-whitespace char.
+    pub fn eof(&self) -> usize {
+        self.src.trim_end().len()
+    }
+
+    /// Renders the lines covered by `loc` with a gutter of line numbers and
+    /// carets under the span.
+    pub fn annotate(&self, loc: &Loc) -> String {
+        let first = self.line_index(loc.0);
+        let last = self.line_index(max(loc.1, loc.0 + 1) - 1);
+        let (line, col) = self.line_col(loc.0);
+        let gutter = (last + 1).to_string().len();
+
+        let mut out = String::new();
+        writeln!(
+            out,
+            "{} --> {}:{}:{}",
+            " ".repeat(gutter),
+            self.name,
+            line,
+            col
+        )
+        .unwrap();
+        writeln!(out, "{} |", " ".repeat(gutter)).unwrap();
+        for i in first..=last {
+            if last - first > MAX_SPAN_LINES && i == first + 2 {
+                writeln!(out, "{} |", ".".repeat(gutter)).unwrap();
+            }
+            if last - first > MAX_SPAN_LINES && i > first + 1 && i + 1 < last {
+                continue;
+            }
+            let text = self.line(i);
+            let start = self.line_starts[i];
+            let from = if i == first {
+                loc.0
+            } else {
+                start + (text.len() - text.trim_start().len())
+            };
+            let to = if i == last {
+                min(loc.1, start + text.len())
+            } else {
+                start + text.len()
+            };
+            let indent: String = text
+                .char_indices()
+                .take_while(|&(j, _)| start + j < from)
+                .map(|(_, c)| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let indent = indent + &" ".repeat(from.saturating_sub(start + text.len()));
+            writeln!(out, "{:>width$} | {}", i + 1, text, width = gutter).unwrap();
+            writeln!(
+                out,
+                "{} | {}{}",
+                " ".repeat(gutter),
+                indent,
+                "^".repeat(max(self.width(from, to), 1))
+            )
+            .unwrap();
+        }
+        out
+    }
+}
+
+#[test]
+fn test_line_col() {
+    let map = SourceMap::new("test", "ab\ncd\n\nあい");
+    assert_eq!(map.line_col(0), (1, 1));
+    assert_eq!(map.line_col(2), (1, 3));
+    assert_eq!(map.line_col(3), (2, 1));
+    assert_eq!(map.line_col(6), (3, 1));
+    assert_eq!(map.line_col(10), (4, 2));
+    assert_eq!(map.line_end(4), 5);
+}
+
+#[test]
+fn test_annotate() {
+    let map = SourceMap::new("test", "x := 1\ny := (2 +\n  3) * z\n");
+    assert_eq!(
+        map.annotate(&Loc(24, 25)),
+        ["  --> test:3:8", "  |", "3 |   3) * z", "  |        ^", ""].join("\n")
+    );
+    assert_eq!(
+        map.annotate(&Loc(12, 21)),
+        [
+            "  --> test:2:6",
+            "  |",
+            "2 | y := (2 +",
+            "  |      ^^^^",
+            "3 |   3) * z",
+            "  |   ^^",
+            ""
+        ]
+        .join("\n")
+    );
+}
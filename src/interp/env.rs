@@ -0,0 +1,107 @@
+use crate::interp::Value;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+// a chain of scopes; inner frames shadow their parents
+#[derive(Clone, Default)]
+pub struct Env(Rc<RefCell<Frame>>);
+
+#[derive(Default)]
+struct Frame {
+    vars: HashMap<String, Value>,
+    parent: Option<Env>,
+    // whether a closure may look up the variables of this scope
+    captured: bool,
+}
+
+impl Env {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // a new, empty scope nested in this one
+    pub fn child(&self) -> Self {
+        Env(Rc::new(RefCell::new(Frame {
+            vars: HashMap::new(),
+            parent: Some(self.clone()),
+            captured: false,
+        })))
+    }
+
+    // this scope for a closure to keep; the closure also sees the parents,
+    // so they are marked too
+    pub fn capture(&self) -> Self {
+        let mut frame = self.0.borrow_mut();
+        if !frame.captured {
+            frame.captured = true;
+            if let Some(ref parent) = frame.parent {
+                parent.capture();
+            }
+        }
+        self.clone()
+    }
+
+    // whether a closure has kept this scope
+    pub fn captured(&self) -> bool {
+        self.0.borrow().captured
+    }
+
+    // binds `var` in this scope, replacing any earlier binding in it
+    pub fn insert(&self, var: String, v: Value) {
+        self.0.borrow_mut().vars.insert(var, v);
+    }
+
+    // whether `var` is bound in this scope itself, not in a parent
+    pub fn binds(&self, var: &str) -> bool {
+        self.0.borrow().vars.contains_key(var)
+    }
+
+    pub fn get(&self, var: &str) -> Option<Value> {
+        let frame = self.0.borrow();
+        match frame.vars.get(var) {
+            Some(v) => Some(v.clone()),
+            None => frame.parent.as_ref().and_then(|p| p.get(var)),
+        }
+    }
+}
+
+// closures are stored in the frames they capture, so printing the values
+// would recurse forever
+impl fmt::Debug for Env {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let frame = self.0.borrow();
+        let mut vars: Vec<_> = frame.vars.keys().collect();
+        vars.sort();
+        f.debug_struct("Env")
+            .field("vars", &vars)
+            .field("parent", &frame.parent)
+            .finish()
+    }
+}
+
+#[test]
+fn test_env_shadowing() {
+    let global = Env::new();
+    global.insert("x".to_string(), Value::Bool(true));
+    let inner = global.child();
+    assert_eq!(inner.get("x").map(|v| v.to_string()), Some("True".into()));
+    inner.insert("x".to_string(), Value::Bool(false));
+    assert_eq!(inner.get("x").map(|v| v.to_string()), Some("False".into()));
+    assert_eq!(global.get("x").map(|v| v.to_string()), Some("True".into()));
+    assert!(inner.get("y").is_none());
+    assert!(inner.binds("x"));
+    assert!(!inner.child().binds("x"));
+}
+
+#[test]
+fn test_env_capture() {
+    let global = Env::new();
+    let inner = global.child();
+    assert!(!global.captured());
+    inner.child().capture();
+    assert!(inner.captured());
+    assert!(global.captured());
+    assert!(!inner.child().captured());
+}
@@ -1,10 +1,30 @@
-use crate::error::print_annot;
-use crate::util::Annot;
+use crate::error::{print_annot, SourceMap};
+use crate::util::{Annot, Integer};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum InterpreterErrorKind {
     DivisionByZero,
+    ModuloByZero,
+    NegativeExponent,
+    Overflow,
+    // calls nested more deeply than `MAX_CALL_DEPTH`
+    TooDeep,
     UnboundVariable(String),
+    // the value of the scrutinee, which no arm matched
+    NonExhaustiveMatch(String),
+    // the value of the right hand side of a destructuring binding, which
+    // its pattern did not match
+    DestructureFailed(String),
+    // a field the record does not have
+    NoField(String),
+    IndexOutOfBounds {
+        index: Integer,
+        len: usize,
+    },
+    TypeMismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
 }
 
 pub type InterpreterError = Annot<InterpreterErrorKind>;
@@ -15,15 +35,30 @@ impl fmt::Display for InterpreterError {
         use self::InterpreterErrorKind::*;
         match self.value {
             DivisionByZero => write!(f, "division by zero"),
+            ModuloByZero => write!(f, "modulo by zero"),
+            NegativeExponent => write!(f, "negative exponent"),
+            Overflow => write!(f, "integer overflow"),
+            TooDeep => write!(f, "calls are nested too deeply"),
             UnboundVariable(ref v) => write!(f, "variable {} is not bound", v),
+            NonExhaustiveMatch(ref v) => write!(f, "no arm matches {}", v),
+            DestructureFailed(ref v) => write!(f, "{} does not match the pattern", v),
+            NoField(ref field) => write!(f, "the record has no field {}", field),
+            IndexOutOfBounds { ref index, len } => {
+                write!(
+                    f,
+                    "index {} is out of bounds for a list of length {}",
+                    index, len
+                )
+            }
+            TypeMismatch { expected, found } => write!(f, "expected {}, found {}", expected, found),
         }
     }
 }
 
 impl InterpreterError {
-    pub fn show_diagnostic(&self, input: &str) {
+    pub fn show_diagnostic(&self, src: &SourceMap) {
         eprintln!("{}", self);
-        print_annot(input, self.loc.clone());
+        print_annot(src, self.loc.clone());
     }
 }
 
@@ -33,7 +68,16 @@ impl StdError for InterpreterError {
         use self::InterpreterErrorKind::*;
         match self.value {
             DivisionByZero => "the right hand expression of the division evaluates to zero",
+            ModuloByZero => "the right hand expression of the modulo evaluates to zero",
+            NegativeExponent => "the exponent evaluates to a negative integer",
+            Overflow => "the result is too large to compute",
+            TooDeep => "the function calls itself too many times without returning",
             UnboundVariable(_) => "variable is not bound",
+            NonExhaustiveMatch(_) => "no pattern of the match accepts the value",
+            DestructureFailed(_) => "the pattern of the binding does not accept the value",
+            NoField(_) => "the record does not have the field",
+            IndexOutOfBounds { .. } => "the index is negative or not less than the list's length",
+            TypeMismatch { .. } => "the value has an unexpected type",
         }
     }
 }
@@ -1,98 +1,762 @@
-use crate::interp::{InterpreterError, InterpreterErrorKind};
-use crate::parser::{Ast, BinOp, UniOp};
-use std::collections::HashMap;
+use crate::interp::{Env, InterpreterError, InterpreterErrorKind};
+use crate::parser::{Arm, Ast, BinOp, BinOpKind, Pattern, UniOp, Variant};
+use crate::util::Integer;
+use im_rc::Vector;
+use std::rc::Rc;
+
+// the largest power computed, in bits; larger ones would take too long and
+// too much memory
+const MAX_POW_BITS: u64 = 1 << 24;
 
 #[derive(Debug, Clone)]
 pub enum Value {
-    Int(i32),
+    Int(Integer),
+    Float(f64),
+    Str(String),
+    Bool(bool),
+    // persistent, so consing and appending share the original list
+    List(Vector<Value>),
+    Tuple(Vec<Value>),
+    // the fields sorted by name
+    Record(Vec<(String, Value)>),
+    // a value built by the constructor `tag` of a `type` definition
+    Data {
+        tag: String,
+        fields: Vec<Value>,
+    },
+    // a constructor that has been given `fields` of its `arity` fields so far
+    Constructor {
+        tag: String,
+        arity: usize,
+        fields: Vec<Value>,
+    },
+    Closure {
+        param: String,
+        body: Rc<Ast>,
+        env: Env,
+    },
+    // a function implemented in Rust, such as `String`
+    Builtin {
+        name: &'static str,
+        fun: fn(Value) -> Result<Value, InterpreterErrorKind>,
+    },
     Void,
 }
 
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        use self::Value::*;
+        match self {
+            Int(_) => "Int",
+            Float(_) => "Float",
+            Str(_) => "String",
+            Bool(_) => "Bool",
+            List(_) => "List",
+            Tuple(_) => "tuple",
+            Record(_) => "record",
+            Data { .. } => "data",
+            Closure { .. } | Builtin { .. } | Constructor { .. } => "function",
+            Void => "()",
+        }
+    }
+}
+
 use std::fmt;
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use self::Value::*;
         match self {
             Int(n) => n.fmt(f),
+            // `{:?}` keeps the fraction of whole floats, e.g. `3.0`
+            Float(n) => write!(f, "{:?}", n),
+            Str(s) => s.fmt(f),
+            Bool(true) => write!(f, "True"),
+            Bool(false) => write!(f, "False"),
+            List(xs) => {
+                write!(f, "[")?;
+                for (i, x) in xs.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    fmt_quoted(x, f)?;
+                }
+                write!(f, "]")
+            }
+            Tuple(xs) => {
+                write!(f, "(")?;
+                for (i, x) in xs.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    fmt_quoted(x, f)?;
+                }
+                write!(f, ")")
+            }
+            Record(fields) if fields.is_empty() => write!(f, "{{}}"),
+            Record(fields) => {
+                write!(f, "{{ ")?;
+                for (i, (name, x)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{} = ", name)?;
+                    fmt_quoted(x, f)?;
+                }
+                write!(f, " }}")
+            }
+            Data { tag, fields } => {
+                tag.fmt(f)?;
+                for field in fields {
+                    match field {
+                        Data { fields, .. } if !fields.is_empty() => write!(f, " ({})", field)?,
+                        field => {
+                            write!(f, " ")?;
+                            fmt_quoted(field, f)?;
+                        }
+                    }
+                }
+                Ok(())
+            }
+            Constructor { tag, .. } => write!(f, "<constructor {}>", tag),
+            Closure { .. } => write!(f, "<function>"),
+            Builtin { name, .. } => write!(f, "<builtin {}>", name),
             Void => write!(f, ""),
         }
     }
 }
 
-pub struct Interpreter(HashMap<String, Value>);
+// strings inside lists and constructors are quoted, so that `["a, b"]`
+// differs from `["a", "b"]`
+fn fmt_quoted(v: &Value, f: &mut fmt::Formatter) -> fmt::Result {
+    match v {
+        Value::Str(s) => write!(f, "{:?}", s),
+        v => write!(f, "{}", v),
+    }
+}
+
+/// How deeply calls may nest before evaluation fails instead of running out
+/// of stack.
+pub const MAX_CALL_DEPTH: usize = 20_000;
+
+/// The stack a thread evaluating programs needs to reach `MAX_CALL_DEPTH`,
+/// leaving each call about 50KB, several times what an unoptimized build
+/// takes for a call in a small function.
+pub const STACK_SIZE: usize = 1 << 30;
+
+pub struct Interpreter {
+    env: Env,
+    // the calls of closures in progress
+    depth: usize,
+}
 
 impl Interpreter {
     pub fn new() -> Self {
-        Interpreter(HashMap::new())
+        let env = Env::new();
+        env.insert(
+            "String".to_string(),
+            Value::Builtin {
+                name: "String",
+                fun: builtin_string,
+            },
+        );
+        Interpreter { env, depth: 0 }
     }
 
     pub fn eval(&mut self, expr: &Ast) -> Result<Value, InterpreterError> {
-        use self::Value::*;
         use crate::parser::AstKind::*;
+        // each call of the program nests a few calls of `eval`, so the
+        // larger arms are kept in their own methods to keep its frame small
         match expr.value {
-            Num(n) => Ok(Value::Int(n)),
+            Num(ref n) => Ok(Value::Int(n.clone())),
+            Float(n) => Ok(Value::Float(n)),
+            Str(ref s) => Ok(Value::Str(s.clone())),
+            Bool(b) => Ok(Value::Bool(b)),
+            List(ref elems) => self.eval_list(elems),
+            Tuple(ref elems) => self.eval_tuple(elems),
+            Record(ref fields) => self.eval_record(fields),
+            Update {
+                ref record,
+                ref fields,
+            } => self.eval_update(record, fields, expr),
+            Field {
+                ref record,
+                ref field,
+            } => self.eval_field(record, field, expr),
             UniOp { ref op, ref e } => {
-                let e = match self.eval(e)? {
-                    Int(e) => e,
-                    Void => unreachable!(),
-                };
-                Ok(self.eval_uniop(op, e))
+                let v = self.eval(e)?;
+                self.eval_uniop(op, v, e)
             }
             BinOp {
                 ref op,
                 ref lhs,
                 ref rhs,
-            } => {
-                let l = match self.eval(lhs)? {
-                    Int(e) => e,
-                    Void => unreachable!(),
-                };
-                let r = match self.eval(rhs)? {
-                    Int(e) => e,
-                    Void => unreachable!(),
-                };
-                self.eval_binop(op, l, r)
-                    .map_err(|e| InterpreterError::new(e, expr.loc.clone()))
+            } if op.value == BinOpKind::And || op.value == BinOpKind::Or => {
+                self.eval_logic(op, lhs, rhs)
             }
-            Bind { ref var, ref body } => {
-                let e = self.eval(body)?;
-                self.0.insert(var.clone(), e);
+            BinOp {
+                ref op,
+                ref lhs,
+                ref rhs,
+            } => self.eval_operator(op, lhs, rhs, expr),
+            Bind {
+                ref var, ref body, ..
+            } => self.eval_bind(var, body),
+            Destructure { ref pat, ref body } => self.eval_destructure(pat, body, expr),
+            TypeDecl { .. } | TypeAlias { .. } => Ok(Value::Void),
+            Data { ref variants, .. } => {
+                self.define(variants);
                 Ok(Value::Void)
             }
-            Var(ref s) => self.0.get(s).cloned().ok_or(InterpreterError::new(
-                InterpreterErrorKind::UnboundVariable(s.clone()),
+            Var(ref s) => self.env.get(s).ok_or_else(|| {
+                InterpreterError::new(
+                    InterpreterErrorKind::UnboundVariable(s.clone()),
+                    expr.loc.clone(),
+                )
+            }),
+            Lambda {
+                ref param,
+                ref body,
+            } => Ok(Value::Closure {
+                param: param.clone(),
+                body: body.clone(),
+                env: self.env.capture(),
+            }),
+            App { ref fun, ref arg } => {
+                let f = self.eval(fun)?;
+                let arg = self.eval(arg)?;
+                self.apply(f, arg, fun)
+            }
+            Index {
+                ref list,
+                ref index,
+            } => self.eval_index(list, index),
+            Match {
+                ref scrutinee,
+                ref arms,
+            } => self.eval_match(scrutinee, arms),
+            If {
+                ref cond,
+                ref then,
+                ref els,
+            } => {
+                if expect_bool(self.eval(cond)?, cond)? {
+                    self.eval(then)
+                } else {
+                    self.eval(els)
+                }
+            }
+            Block(ref stmts) => {
+                // bindings made in the block are dropped at its end
+                let scope = self.env.child();
+                self.in_scope(scope, |interp| interp.eval_stmts(stmts))
+            }
+            Program(ref stmts) => self.eval_stmts(stmts),
+        }
+    }
+
+    // the value of the last statement
+    fn eval_stmts(&mut self, stmts: &[Ast]) -> Result<Value, InterpreterError> {
+        let mut last = Value::Void;
+        for stmt in stmts {
+            last = self.eval(stmt)?;
+        }
+        Ok(last)
+    }
+
+    fn eval_list(&mut self, elems: &[Ast]) -> Result<Value, InterpreterError> {
+        let mut xs = Vector::new();
+        for e in elems {
+            xs.push_back(self.eval(e)?);
+        }
+        Ok(Value::List(xs))
+    }
+
+    fn eval_tuple(&mut self, elems: &[Ast]) -> Result<Value, InterpreterError> {
+        Ok(Value::Tuple(
+            elems
+                .iter()
+                .map(|e| self.eval(e))
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+
+    fn eval_record(&mut self, fields: &[(String, Ast)]) -> Result<Value, InterpreterError> {
+        let mut record = Vec::new();
+        for (name, e) in fields {
+            record.push((name.clone(), self.eval(e)?));
+        }
+        record.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(Value::Record(record))
+    }
+
+    fn eval_update(
+        &mut self,
+        record: &Ast,
+        fields: &[(String, Ast)],
+        expr: &Ast,
+    ) -> Result<Value, InterpreterError> {
+        let mut updated = match self.eval(record)? {
+            Value::Record(updated) => updated,
+            v => return Err(type_mismatch("record", &v, record)),
+        };
+        for (name, e) in fields {
+            let v = self.eval(e)?;
+            match updated.iter_mut().find(|(field, _)| field == name) {
+                Some((_, old)) => *old = v,
+                None => return Err(no_field(name, expr)),
+            }
+        }
+        Ok(Value::Record(updated))
+    }
+
+    fn eval_field(
+        &mut self,
+        record: &Ast,
+        field: &str,
+        expr: &Ast,
+    ) -> Result<Value, InterpreterError> {
+        match self.eval(record)? {
+            Value::Record(fields) => fields
+                .into_iter()
+                .find(|(name, _)| name == field)
+                .map(|(_, v)| v)
+                .ok_or_else(|| no_field(field, expr)),
+            v => Err(type_mismatch("record", &v, record)),
+        }
+    }
+
+    // `&&` and `||`
+    fn eval_logic(&mut self, op: &BinOp, lhs: &Ast, rhs: &Ast) -> Result<Value, InterpreterError> {
+        // the right hand side is only evaluated when the left does not decide
+        let l = expect_bool(self.eval(lhs)?, lhs)?;
+        if l == (op.value == BinOpKind::Or) {
+            return Ok(Value::Bool(l));
+        }
+        Ok(Value::Bool(expect_bool(self.eval(rhs)?, rhs)?))
+    }
+
+    // the binary operators other than `&&` and `||`
+    fn eval_operator(
+        &mut self,
+        op: &BinOp,
+        lhs: &Ast,
+        rhs: &Ast,
+        expr: &Ast,
+    ) -> Result<Value, InterpreterError> {
+        let (l, r) = match (op.value, self.eval(lhs)?, self.eval(rhs)?) {
+            (BinOpKind::Concat, Value::Str(l), Value::Str(r)) => return Ok(Value::Str(l + &r)),
+            (BinOpKind::Concat, Value::List(mut l), Value::List(r)) => {
+                l.append(r);
+                return Ok(Value::List(l));
+            }
+            (BinOpKind::Concat, Value::Str(_), r) => return Err(type_mismatch("String", &r, rhs)),
+            (BinOpKind::Concat, Value::List(_), r) => return Err(type_mismatch("List", &r, rhs)),
+            (BinOpKind::Concat, l, _) => return Err(type_mismatch("String", &l, lhs)),
+            (BinOpKind::Cons, x, Value::List(mut xs)) => {
+                xs.push_front(x);
+                return Ok(Value::List(xs));
+            }
+            (BinOpKind::Cons, _, r) => return Err(type_mismatch("List", &r, rhs)),
+            (op, l, r) if op.is_comparison() => {
+                return match compare(op, &l, &r) {
+                    Some(b) => Ok(Value::Bool(b)),
+                    None if l.type_name() == r.type_name() => {
+                        Err(type_mismatch("comparable value", &l, lhs))
+                    }
+                    None => Err(type_mismatch(l.type_name(), &r, rhs)),
+                }
+            }
+            (_, Value::Int(l), Value::Int(r)) => {
+                return self
+                    .eval_binop(op, l, r)
+                    .map_err(|e| InterpreterError::new(e, expr.loc.clone()))
+            }
+            (_, l, r) => (expect_float(l, lhs)?, expect_float(r, rhs)?),
+        };
+        Ok(self.eval_float_binop(op, l, r))
+    }
+
+    fn eval_bind(&mut self, var: &str, body: &Ast) -> Result<Value, InterpreterError> {
+        self.rebinding(std::iter::once(var));
+        let e = self.eval(body)?;
+        self.env.insert(var.to_string(), e);
+        Ok(Value::Void)
+    }
+
+    fn eval_destructure(
+        &mut self,
+        pat: &Pattern,
+        body: &Ast,
+        expr: &Ast,
+    ) -> Result<Value, InterpreterError> {
+        let v = self.eval(body)?;
+        let mut bindings = Vec::new();
+        if !match_pattern(pat, &v, &mut bindings) {
+            return Err(InterpreterError::new(
+                InterpreterErrorKind::DestructureFailed(v.to_string()),
                 expr.loc.clone(),
+            ));
+        }
+        self.rebinding(bindings.iter().map(|(var, _)| var.as_str()));
+        for (var, v) in bindings {
+            self.env.insert(var, v);
+        }
+        Ok(Value::Void)
+    }
+
+    // binds the constructors of a `type` definition
+    fn define(&mut self, variants: &[Variant]) {
+        self.rebinding(variants.iter().map(|variant| variant.name.as_str()));
+        for variant in variants {
+            let tag = variant.name.clone();
+            let ctor = match variant.fields.len() {
+                0 => Value::Data {
+                    tag: tag.clone(),
+                    fields: Vec::new(),
+                },
+                arity => Value::Constructor {
+                    tag: tag.clone(),
+                    arity,
+                    fields: Vec::new(),
+                },
+            };
+            self.env.insert(tag, ctor);
+        }
+    }
+
+    fn eval_index(&mut self, list: &Ast, index: &Ast) -> Result<Value, InterpreterError> {
+        let xs = match self.eval(list)? {
+            Value::List(xs) => xs,
+            v => return Err(type_mismatch("List", &v, list)),
+        };
+        let i = match self.eval(index)? {
+            Value::Int(i) => i,
+            v => return Err(type_mismatch("Int", &v, index)),
+        };
+        match i.to_usize().and_then(|i| xs.get(i)) {
+            Some(x) => Ok(x.clone()),
+            None => Err(InterpreterError::new(
+                InterpreterErrorKind::IndexOutOfBounds {
+                    index: i,
+                    len: xs.len(),
+                },
+                index.loc.clone(),
             )),
         }
     }
 
-    fn eval_uniop(&mut self, op: &UniOp, n: i32) -> Value {
+    fn eval_match(&mut self, scrutinee: &Ast, arms: &[Arm]) -> Result<Value, InterpreterError> {
+        let v = self.eval(scrutinee)?;
+        for arm in arms {
+            let mut bindings = Vec::new();
+            if !match_pattern(&arm.pat, &v, &mut bindings) {
+                continue;
+            }
+            let scope = self.env.child();
+            for (var, v) in bindings {
+                scope.insert(var, v);
+            }
+            // a failing guard falls through to the next arm
+            let taken = match arm.guard {
+                Some(ref guard) => self.in_scope(scope.clone(), |interp| {
+                    expect_bool(interp.eval(guard)?, guard)
+                })?,
+                None => true,
+            };
+            if taken {
+                return self.in_scope(scope, |interp| interp.eval(&arm.body));
+            }
+        }
+        Err(InterpreterError::new(
+            InterpreterErrorKind::NonExhaustiveMatch(v.to_string()),
+            scrutinee.loc.clone(),
+        ))
+    }
+
+    // closures capture the scope they are made in, so a binding that would
+    // replace one a closure may see goes into a new scope instead
+    fn rebinding<'a>(&mut self, mut vars: impl Iterator<Item = &'a str>) {
+        if self.env.captured() && vars.any(|var| self.env.binds(var)) {
+            self.env = self.env.child();
+        }
+    }
+
+    // runs `f` in `scope`, returning to the current scope after
+    fn in_scope<T>(&mut self, scope: Env, f: impl FnOnce(&mut Self) -> T) -> T {
+        let outer = std::mem::replace(&mut self.env, scope);
+        let result = f(self);
+        self.env = outer;
+        result
+    }
+
+    // calls the function value `f`, which was evaluated from `fun`
+    fn apply(&mut self, f: Value, arg: Value, fun: &Ast) -> Result<Value, InterpreterError> {
+        match f {
+            Value::Closure { param, body, env } => {
+                if self.depth == MAX_CALL_DEPTH {
+                    return Err(InterpreterError::new(
+                        InterpreterErrorKind::TooDeep,
+                        fun.loc.clone(),
+                    ));
+                }
+                let scope = env.child();
+                scope.insert(param, arg);
+                self.depth += 1;
+                let result = self.in_scope(scope, |interp| interp.eval(&body));
+                self.depth -= 1;
+                result
+            }
+            Value::Constructor {
+                tag,
+                arity,
+                mut fields,
+            } => {
+                fields.push(arg);
+                if fields.len() == arity {
+                    Ok(Value::Data { tag, fields })
+                } else {
+                    Ok(Value::Constructor { tag, arity, fields })
+                }
+            }
+            Value::Builtin { fun: builtin, .. } => {
+                builtin(arg).map_err(|e| InterpreterError::new(e, fun.loc.clone()))
+            }
+            f => Err(type_mismatch("function", &f, fun)),
+        }
+    }
+
+    fn eval_uniop(&mut self, op: &UniOp, v: Value, e: &Ast) -> Result<Value, InterpreterError> {
         use crate::parser::UniOpKind::*;
-        match op.value {
-            Plus => Value::Int(n),
-            Minus => Value::Int(-n),
+        match (&op.value, v) {
+            (Plus, v @ Value::Int(_)) | (Plus, v @ Value::Float(_)) => Ok(v),
+            (Minus, Value::Int(n)) => Ok(Value::Int(n.neg())),
+            (Minus, Value::Float(n)) => Ok(Value::Float(-n)),
+            (Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+            (Not, v) => Err(type_mismatch("Bool", &v, e)),
+            (_, v) => Err(type_mismatch("number", &v, e)),
         }
     }
 
     fn eval_binop(
         &mut self,
         op: &BinOp,
-        lhs: i32,
-        rhs: i32,
+        lhs: Integer,
+        rhs: Integer,
     ) -> Result<Value, InterpreterErrorKind> {
         use crate::parser::BinOpKind::*;
-        match op.value {
-            Add => Ok(Value::Int(lhs + rhs)),
-            Sub => Ok(Value::Int(lhs - rhs)),
-            Mul => Ok(Value::Int(lhs * rhs)),
+        let n = match op.value {
+            Add => lhs.add(&rhs),
+            Sub => lhs.sub(&rhs),
+            Mul => lhs.mul(&rhs),
             Div => {
-                if rhs == 0 {
-                    Err(InterpreterErrorKind::DivisionByZero)
+                if rhs.is_zero() {
+                    return Err(InterpreterErrorKind::DivisionByZero);
+                }
+                lhs.div(&rhs)
+            }
+            Mod => {
+                if rhs.is_zero() {
+                    return Err(InterpreterErrorKind::ModuloByZero);
+                }
+                lhs.rem(&rhs)
+            }
+            Pow => {
+                if rhs.is_negative() {
+                    return Err(InterpreterErrorKind::NegativeExponent);
+                }
+                if lhs.bits() <= 1 {
+                    // 0, 1 and -1 never grow, and a power of -1 only
+                    // depends on whether the exponent is even
+                    let exp = if rhs.is_zero() {
+                        0
+                    } else if rhs.is_even() {
+                        2
+                    } else {
+                        1
+                    };
+                    lhs.pow(exp)
                 } else {
-                    Ok(Value::Int(lhs / rhs))
+                    // the result has about `bits(lhs) * rhs` bits
+                    let exp = rhs
+                        .to_u32()
+                        .filter(|&exp| lhs.bits().saturating_mul(exp.into()) <= MAX_POW_BITS)
+                        .ok_or(InterpreterErrorKind::Overflow)?;
+                    lhs.pow(exp)
                 }
             }
+            _ => unreachable!(),
+        };
+        Ok(Value::Int(n))
+    }
+
+    // floats follow IEEE 754, so dividing by zero gives an infinity or NaN
+    fn eval_float_binop(&mut self, op: &BinOp, lhs: f64, rhs: f64) -> Value {
+        use crate::parser::BinOpKind::*;
+        let n = match op.value {
+            Add => lhs + rhs,
+            Sub => lhs - rhs,
+            Mul => lhs * rhs,
+            Div => lhs / rhs,
+            Mod => lhs % rhs,
+            Pow => lhs.powf(rhs),
+            _ => unreachable!(),
+        };
+        Value::Float(n)
+    }
+}
+
+// whether `v` matches `pat`, collecting the variables it binds
+fn match_pattern(pat: &Pattern, v: &Value, bindings: &mut Vec<(String, Value)>) -> bool {
+    use crate::parser::PatternKind::*;
+    match (&pat.value, v) {
+        (Wildcard, _) => true,
+        (Var(var), v) => {
+            bindings.push((var.clone(), v.clone()));
+            true
+        }
+        (Num(n), Value::Int(m)) => n == m,
+        (Str(s), Value::Str(t)) => s == t,
+        (Bool(b), Value::Bool(c)) => b == c,
+        (List(pats), Value::List(xs)) => {
+            pats.len() == xs.len()
+                && pats
+                    .iter()
+                    .zip(xs)
+                    .all(|(pat, x)| match_pattern(pat, x, bindings))
+        }
+        (Tuple(pats), Value::Tuple(xs)) => {
+            pats.len() == xs.len()
+                && pats
+                    .iter()
+                    .zip(xs)
+                    .all(|(pat, x)| match_pattern(pat, x, bindings))
+        }
+        (Record(pats), Value::Record(fields)) => {
+            pats.iter().all(
+                |(name, pat)| match fields.iter().find(|(field, _)| field == name) {
+                    Some((_, field)) => match_pattern(pat, field, bindings),
+                    None => false,
+                },
+            )
+        }
+        (Cons(head, tail), Value::List(xs)) => match xs.front() {
+            Some(x) => {
+                match_pattern(head, x, bindings)
+                    && match_pattern(tail, &Value::List(xs.skip(1)), bindings)
+            }
+            None => false,
+        },
+        (Ctor(ctor, pats), Value::Data { tag, fields }) => {
+            ctor == tag
+                && pats.len() == fields.len()
+                && pats
+                    .iter()
+                    .zip(fields)
+                    .all(|(pat, field)| match_pattern(pat, field, bindings))
         }
+        _ => false,
+    }
+}
+
+// applies the comparison `op`, or gives `None` when `l` and `r` cannot be
+// compared. An Int compared with a Float is promoted, and NaN is unordered.
+fn compare(op: BinOpKind, l: &Value, r: &Value) -> Option<bool> {
+    use std::cmp::Ordering::*;
+    // lists, tuples, records and data are equal when their elements are,
+    // but have no order
+    let equal = |(x, y)| compare(BinOpKind::Eq, x, y) == Some(true);
+    let eq = match (l, r) {
+        (Value::List(a), Value::List(b)) => Some(a.len() == b.len() && a.iter().zip(b).all(equal)),
+        (Value::Tuple(a), Value::Tuple(b)) => {
+            Some(a.len() == b.len() && a.iter().zip(b).all(equal))
+        }
+        (Value::Record(a), Value::Record(b)) => Some(
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b)
+                    .all(|((x, v), (y, w))| x == y && equal((v, w))),
+        ),
+        (
+            Value::Data {
+                tag: t1,
+                fields: f1,
+            },
+            Value::Data {
+                tag: t2,
+                fields: f2,
+            },
+        ) => Some(t1 == t2 && f1.len() == f2.len() && f1.iter().zip(f2).all(equal)),
+        _ => None,
+    };
+    if let Some(eq) = eq {
+        return match op {
+            BinOpKind::Eq => Some(eq),
+            BinOpKind::Ne => Some(!eq),
+            _ => None,
+        };
+    }
+    let ord = match (l, r) {
+        (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+        (Value::Str(a), Value::Str(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (l, r) => as_f64(l)?.partial_cmp(&as_f64(r)?),
+    };
+    Some(match op {
+        BinOpKind::Eq => ord == Some(Equal),
+        BinOpKind::Ne => ord != Some(Equal),
+        BinOpKind::Lt => ord == Some(Less),
+        BinOpKind::Le => ord == Some(Less) || ord == Some(Equal),
+        BinOpKind::Gt => ord == Some(Greater),
+        BinOpKind::Ge => ord == Some(Greater) || ord == Some(Equal),
+        _ => unreachable!(),
+    })
+}
+
+// `String n` shows the Int `n` in decimal
+fn builtin_string(v: Value) -> Result<Value, InterpreterErrorKind> {
+    match v {
+        Value::Int(n) => Ok(Value::Str(n.to_string())),
+        v => Err(InterpreterErrorKind::TypeMismatch {
+            expected: "Int",
+            found: v.type_name(),
+        }),
+    }
+}
+
+fn no_field(field: &str, expr: &Ast) -> InterpreterError {
+    InterpreterError::new(
+        InterpreterErrorKind::NoField(field.to_string()),
+        expr.loc.clone(),
+    )
+}
+
+fn type_mismatch(expected: &'static str, found: &Value, expr: &Ast) -> InterpreterError {
+    InterpreterError::new(
+        InterpreterErrorKind::TypeMismatch {
+            expected,
+            found: found.type_name(),
+        },
+        expr.loc.clone(),
+    )
+}
+
+// an Int mixed with a Float in arithmetic is promoted to Float
+fn as_f64(v: &Value) -> Option<f64> {
+    match v {
+        Value::Int(n) => Some(n.to_f64()),
+        Value::Float(n) => Some(*n),
+        _ => None,
+    }
+}
+
+fn expect_float(v: Value, expr: &Ast) -> Result<f64, InterpreterError> {
+    as_f64(&v).ok_or_else(|| type_mismatch("number", &v, expr))
+}
+
+fn expect_bool(v: Value, expr: &Ast) -> Result<bool, InterpreterError> {
+    match v {
+        Value::Bool(b) => Ok(b),
+        v => Err(type_mismatch("Bool", &v, expr)),
     }
 }
 
@@ -112,7 +776,7 @@ fn test_eval() {
 
     let result = match interp.eval(&ast).unwrap() {
         Value::Int(n) => n,
-        Value::Void => unreachable!(),
+        _ => unreachable!(),
     };
     assert_eq!(result, 3);
 }
@@ -121,7 +785,7 @@ fn test_eval() {
 fn test_eval_in_paren() {
     use crate::lexer::Loc;
     let mut interp = Interpreter::new();
-    use crate::parser::{Ast, BinOp, UniOp};
+    use crate::parser::{BinOp, UniOp};
 
     // "2 + (-1)"
     let ast = Ast::binop(
@@ -133,7 +797,7 @@ fn test_eval_in_paren() {
 
     let result = match interp.eval(&ast).unwrap() {
         Value::Int(n) => n,
-        Value::Void => unreachable!(),
+        _ => unreachable!(),
     };
     assert_eq!(result, 1);
 }
@@ -164,7 +828,7 @@ fn test_eval_in_0() {
 
     let result = match interp.eval(&ast).unwrap() {
         Value::Int(n) => n,
-        Value::Void => unreachable!(),
+        _ => unreachable!(),
     };
     assert_eq!(result, -3);
 }
@@ -188,7 +852,499 @@ fn test_eval_in_var() {
     interp.eval(&ast).unwrap();
     let r_call = match interp.eval(&call).unwrap() {
         Value::Int(n) => n,
-        Value::Void => unreachable!(),
+        _ => unreachable!(),
     };
     assert_eq!(r_call, 42);
 }
+
+#[test]
+fn test_eval_program() {
+    let mut interp = Interpreter::new();
+
+    let ast = "hoge := 40\nhoge + 2\n".parse::<Ast>().unwrap();
+
+    let result = match interp.eval(&ast).unwrap() {
+        Value::Int(n) => n,
+        _ => unreachable!(),
+    };
+    assert_eq!(result, 42);
+}
+
+#[test]
+fn test_eval_curried_closure() {
+    let mut interp = Interpreter::new();
+
+    let ast = "sub := x n => x - n\nten := sub 10\nk := 5\nten k\n"
+        .parse::<Ast>()
+        .unwrap();
+
+    let result = match interp.eval(&ast).unwrap() {
+        Value::Int(n) => n,
+        _ => unreachable!(),
+    };
+    assert_eq!(result, 5);
+}
+
+#[test]
+fn test_eval_not_a_function() {
+    use crate::lexer::Loc;
+    let mut interp = Interpreter::new();
+
+    let ast = "1 2".parse::<Ast>().unwrap();
+
+    assert_eq!(
+        interp.eval(&ast).unwrap_err(),
+        InterpreterError::new(
+            InterpreterErrorKind::TypeMismatch {
+                expected: "function",
+                found: "Int"
+            },
+            Loc(0, 1)
+        )
+    );
+}
+
+#[test]
+fn test_eval_mod_pow() {
+    use crate::lexer::Loc;
+    let mut interp = Interpreter::new();
+
+    let ast = "-2 ^ 3 ^ 2 % 5 + 9 % 4".parse::<Ast>().unwrap();
+    let result = match interp.eval(&ast).unwrap() {
+        Value::Int(n) => n,
+        _ => unreachable!(),
+    };
+    assert_eq!(result, -2 + 1);
+
+    let ast = "3 % (1 - 1)".parse::<Ast>().unwrap();
+    assert_eq!(
+        interp.eval(&ast).unwrap_err(),
+        InterpreterError::new(InterpreterErrorKind::ModuloByZero, Loc(0, 10))
+    );
+
+    let ast = "2 ^ -1".parse::<Ast>().unwrap();
+    assert_eq!(
+        interp.eval(&ast).unwrap_err(),
+        InterpreterError::new(InterpreterErrorKind::NegativeExponent, Loc(0, 6))
+    );
+}
+
+#[test]
+fn test_eval_bignum() {
+    use crate::lexer::Loc;
+    let mut interp = Interpreter::new();
+
+    for (src, expected) in &[
+        ("2147483647 + 1", "2147483648"),
+        ("9223372036854775807 + 1 - 1", "9223372036854775807"),
+        (
+            "2 ^ 64 * -(2 ^ 64)",
+            "-340282366920938463463374607431768211456",
+        ),
+        ("(-9223372036854775807 - 1) / -1", "9223372036854775808"),
+        ("100000000000000000000 % 7", "2"),
+    ] {
+        let ast = src.parse::<Ast>().unwrap();
+        assert_eq!(interp.eval(&ast).unwrap().to_string(), *expected);
+    }
+
+    // powers of 0, 1 and -1 stay small whatever the exponent
+    for (src, expected) in &[
+        ("1 ^ 4294967296", "1"),
+        ("0 ^ 5000000000", "0"),
+        ("0 ^ 0", "1"),
+        ("(-1) ^ 4294967297", "-1"),
+        ("(-1) ^ 100000000000000000000", "1"),
+    ] {
+        let ast = src.parse::<Ast>().unwrap();
+        assert_eq!(interp.eval(&ast).unwrap().to_string(), *expected);
+    }
+
+    let ast = "2 ^ 100000 % 1000".parse::<Ast>().unwrap();
+    assert_eq!(interp.eval(&ast).unwrap().to_string(), "376");
+
+    let ast = "2 ^ 4000000000".parse::<Ast>().unwrap();
+    assert_eq!(
+        interp.eval(&ast).unwrap_err(),
+        InterpreterError::new(InterpreterErrorKind::Overflow, Loc(0, 14))
+    );
+}
+
+#[test]
+fn test_eval_float() {
+    let mut interp = Interpreter::new();
+
+    for (src, expected) in &[
+        ("1.5 + 1", "2.5"),
+        ("7 / 2", "3"),
+        ("7 / 2.0", "3.5"),
+        ("-0.5 * 4", "-2.0"),
+        ("2 ^ 0.5 ^ 2", "1.189207115002721"),
+        ("1 / 0.0", "inf"),
+        ("0xff + 1_000", "1255"),
+    ] {
+        let ast = src.parse::<Ast>().unwrap();
+        assert_eq!(interp.eval(&ast).unwrap().to_string(), *expected);
+    }
+}
+
+#[test]
+fn test_eval_string() {
+    let mut interp = Interpreter::new();
+
+    let ast = "n := 15\n\"fizz\" ++ \"buzz\\n\" ++ String (n * 2)"
+        .parse::<Ast>()
+        .unwrap();
+
+    let result = match interp.eval(&ast).unwrap() {
+        Value::Str(s) => s,
+        _ => unreachable!(),
+    };
+    assert_eq!(result, "fizzbuzz\n30");
+}
+
+#[test]
+fn test_eval_match() {
+    use crate::lexer::Loc;
+    let mut interp = Interpreter::new();
+
+    let ast = "fizz := n => match n % 15\n  | 0 => \"fizzbuzz\"\n  | -1 => \"never\"\n  | m => String m\nfizz 30 ++ fizz 7"
+        .parse::<Ast>()
+        .unwrap();
+    let result = match interp.eval(&ast).unwrap() {
+        Value::Str(s) => s,
+        _ => unreachable!(),
+    };
+    assert_eq!(result, "fizzbuzz7");
+
+    // the binding of `m` does not leak out of the arm
+    let ast = "m := 1\nk := match 5 | m => m * 2\nm + k"
+        .parse::<Ast>()
+        .unwrap();
+    let result = match interp.eval(&ast).unwrap() {
+        Value::Int(n) => n,
+        _ => unreachable!(),
+    };
+    assert_eq!(result, 11);
+
+    let ast = "match \"b\" | \"a\" => 1".parse::<Ast>().unwrap();
+    assert_eq!(
+        interp.eval(&ast).unwrap_err(),
+        InterpreterError::new(
+            InterpreterErrorKind::NonExhaustiveMatch("b".to_string()),
+            Loc(6, 9)
+        )
+    );
+}
+
+#[test]
+fn test_eval_bool() {
+    let mut interp = Interpreter::new();
+
+    let ast = "x := 3\nif x >= 3 && !(x == 4) || 1 / 0 == 0 then x < 3.5 else False"
+        .parse::<Ast>()
+        .unwrap();
+    let result = match interp.eval(&ast).unwrap() {
+        Value::Bool(b) => b,
+        _ => unreachable!(),
+    };
+    assert!(result);
+
+    // `||` does not evaluate its right hand side once the left is True
+    let ast = "\"a\" < \"b\" || 1 / 0 == 0".parse::<Ast>().unwrap();
+    assert_eq!(interp.eval(&ast).unwrap().to_string(), "True");
+
+    let ast = "False && 1 / 0 == 0".parse::<Ast>().unwrap();
+    assert_eq!(interp.eval(&ast).unwrap().to_string(), "False");
+}
+
+#[test]
+fn test_eval_int_condition() {
+    use crate::lexer::Loc;
+    let mut interp = Interpreter::new();
+
+    let ast = "if 1 then 2 else 3".parse::<Ast>().unwrap();
+    assert_eq!(
+        interp.eval(&ast).unwrap_err(),
+        InterpreterError::new(
+            InterpreterErrorKind::TypeMismatch {
+                expected: "Bool",
+                found: "Int",
+            },
+            Loc(3, 4)
+        )
+    );
+
+    let ast = "True && 0".parse::<Ast>().unwrap();
+    assert_eq!(
+        interp.eval(&ast).unwrap_err(),
+        InterpreterError::new(
+            InterpreterErrorKind::TypeMismatch {
+                expected: "Bool",
+                found: "Int",
+            },
+            Loc(8, 9)
+        )
+    );
+}
+
+#[test]
+fn test_eval_block() {
+    let mut interp = Interpreter::new();
+
+    let src = "m := 100
+fizzbuzz := n =>
+    m := n % 15
+    match m
+        0 => \"fizzbuzz\"
+        5 =>
+            s := \"bu\"
+            s ++ \"zz\"
+        _ => String n
+fizzbuzz 30 ++ fizzbuzz 20 ++ fizzbuzz 7 ++ String m
+";
+    let ast = src.parse::<Ast>().unwrap();
+    let result = match interp.eval(&ast).unwrap() {
+        Value::Str(s) => s,
+        _ => unreachable!(),
+    };
+    assert_eq!(result, "fizzbuzzbuzz7100");
+}
+
+#[test]
+fn test_eval_scope() {
+    use crate::lexer::Loc;
+
+    let mut interp = Interpreter::new();
+
+    let src = "adder := n =>
+    k := n
+    x => x + k
+add2 := adder 2
+k := 10
+fact := n => if n == 0 then 1 else n * fact (n - 1)
+add2 1 + fact 5 + k
+";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(interp.eval(&ast).unwrap().to_string(), "133");
+
+    // block bindings are gone once the block ends
+    let ast = "f := n =>\n    j := n\n    j\nf 1 + j"
+        .parse::<Ast>()
+        .unwrap();
+    assert_eq!(
+        interp.eval(&ast).unwrap_err(),
+        InterpreterError::new(
+            InterpreterErrorKind::UnboundVariable("j".into()),
+            Loc(33, 34)
+        )
+    );
+}
+
+#[test]
+fn test_eval_deep_recursion() {
+    use crate::lexer::Loc;
+
+    // as in `main`, evaluation runs on a thread with a large stack
+    let deep = std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(|| {
+            let mut interp = Interpreter::new();
+            let src = "fn count n => if n == 0 then 0 else 1 + count (n - 1)\ncount 5000";
+            let ast = src.parse::<Ast>().unwrap();
+            assert_eq!(interp.eval(&ast).unwrap().to_string(), "5000");
+
+            let ast = "fn loop n => loop n\nloop 0".parse::<Ast>().unwrap();
+            assert_eq!(
+                interp.eval(&ast).unwrap_err(),
+                InterpreterError::new(InterpreterErrorKind::TooDeep, Loc(13, 17))
+            );
+        })
+        .unwrap();
+    deep.join().unwrap();
+}
+
+#[test]
+fn test_eval_rebinding() {
+    use crate::typeck::{Ty, TypeChecker};
+
+    // a closure keeps the value it captured when the name is bound again
+    let src = "x := 1\nf := y => x\nx := \"s\"\nf 0 + 1";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(TypeChecker::new().check(&ast).unwrap(), Ty::Int);
+    let mut interp = Interpreter::new();
+    assert_eq!(interp.eval(&ast).unwrap().to_string(), "2");
+    let ast = "x".parse::<Ast>().unwrap();
+    assert_eq!(interp.eval(&ast).unwrap().to_string(), "s");
+
+    // also a closure made in a nested scope
+    let src = "x := 1\nf := match 0 | _ => y => x\nx := 2\nf 0";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(Interpreter::new().eval(&ast).unwrap().to_string(), "1");
+
+    // without a closure to keep the old value, it is replaced in place
+    let mut interp = Interpreter::new();
+    let ast = "x := 1\nx := 2\nx := x + 1".parse::<Ast>().unwrap();
+    interp.eval(&ast).unwrap();
+    assert_eq!(
+        format!("{:?}", interp.env),
+        "Env { vars: [\"String\", \"x\"], parent: None }"
+    );
+
+    let src = "g := n =>\n    x := n\n    f := y => x\n    x := \"s\"\n    f 0 + 1\ng 1";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(TypeChecker::new().check(&ast).unwrap(), Ty::Int);
+    assert_eq!(Interpreter::new().eval(&ast).unwrap().to_string(), "2");
+
+    // a rebound function still calls itself
+    let src = "f := n => n\nf := n => if n == 0 then 0 else f (n - 1)\nf 3";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(Interpreter::new().eval(&ast).unwrap().to_string(), "0");
+}
+
+#[test]
+fn test_eval_list() {
+    use crate::lexer::Loc;
+    let mut interp = Interpreter::new();
+
+    let src = "fn range n => if n == 0 then [] else range (n - 1) ++ [n]
+fn sum xs => match xs
+    [] => 0
+    x :: rest => x + sum rest
+xs := range 10
+ys := 0 :: xs
+String (sum ys) ++ String xs[0] ++ String ys[0]
+";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(interp.eval(&ast).unwrap().to_string(), "5510");
+
+    let ast = "[[\"a\"], []] ++ [[\"b, c\"]]".parse::<Ast>().unwrap();
+    assert_eq!(
+        interp.eval(&ast).unwrap().to_string(),
+        "[[\"a\"], [], [\"b, c\"]]"
+    );
+
+    let ast = "match [1, 2] | [a] => a | [a, b] => a + b"
+        .parse::<Ast>()
+        .unwrap();
+    assert_eq!(interp.eval(&ast).unwrap().to_string(), "3");
+
+    let ast = "xs[-1]".parse::<Ast>().unwrap();
+    assert_eq!(
+        interp.eval(&ast).unwrap_err(),
+        InterpreterError::new(
+            InterpreterErrorKind::IndexOutOfBounds {
+                index: Integer::from(-1),
+                len: 10,
+            },
+            Loc(3, 5)
+        )
+    );
+}
+
+#[test]
+fn test_eval_data() {
+    let mut interp = Interpreter::new();
+
+    let src = "type Shape = Circle Int | Rect Int Int
+type Tree =
+    | Leaf
+    | Node Tree String Tree
+fn area s => match s
+    Circle r => 3 * r * r
+    Rect w h => w * h
+String (area (Circle 2) + area (Rect 3 4))
+";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(interp.eval(&ast).unwrap().to_string(), "24");
+
+    let ast = "Node Leaf \"a\" (Node Leaf \"b\" Leaf)"
+        .parse::<Ast>()
+        .unwrap();
+    assert_eq!(
+        interp.eval(&ast).unwrap().to_string(),
+        "Node Leaf \"a\" (Node Leaf \"b\" Leaf)"
+    );
+
+    let ast = "[Rect 1 2 == Rect 1 2, Rect 1 2 == Rect 2 1, Leaf == Leaf]"
+        .parse::<Ast>()
+        .unwrap();
+    assert_eq!(
+        interp.eval(&ast).unwrap().to_string(),
+        "[True, False, True]"
+    );
+
+    let ast = "Rect 1".parse::<Ast>().unwrap();
+    assert_eq!(interp.eval(&ast).unwrap().to_string(), "<constructor Rect>");
+}
+
+#[test]
+fn test_eval_guard() {
+    let mut interp = Interpreter::new();
+
+    let src = "fn isSmall n => n < 10
+fn classify n => match n
+    0 => \"zero\"
+    isSmall x => \"small\"
+    x if x % 2 == 0 => \"even\"
+    _ => \"odd\"
+[classify 0, classify 7, classify 12, classify 13]";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(
+        interp.eval(&ast).unwrap().to_string(),
+        "[\"zero\", \"small\", \"even\", \"odd\"]"
+    );
+
+    // the bindings of a failed arm do not leak into the next one
+    let ast = "x := 1\nmatch 5 | x if x > 9 => 0 | _ => x"
+        .parse::<Ast>()
+        .unwrap();
+    assert_eq!(interp.eval(&ast).unwrap().to_string(), "1");
+}
+
+#[test]
+fn test_eval_record() {
+    let mut interp = Interpreter::new();
+
+    let src = "p := { name = \"a\", age = 3 }
+q := { p | age = p.age + 1 }
+match q
+    { age = 3 } => \"old\"
+    { name, age } => name ++ String age";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(interp.eval(&ast).unwrap().to_string(), "a4");
+
+    // fields are shown and compared by name
+    let ast = "q".parse::<Ast>().unwrap();
+    assert_eq!(
+        interp.eval(&ast).unwrap().to_string(),
+        "{ age = 4, name = \"a\" }"
+    );
+    let ast = "{ age = 4, name = \"a\" } == q".parse::<Ast>().unwrap();
+    assert_eq!(interp.eval(&ast).unwrap().to_string(), "True");
+}
+
+#[test]
+fn test_eval_tuple() {
+    use crate::lexer::Loc;
+    let mut interp = Interpreter::new();
+
+    let src = "fn divmod a b => (a / b, a % b)
+(q, r) := divmod 9 4
+fn swap (a, b) => (b, a)
+[swap (q, \"r\"), swap (r, \"q\")]";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(
+        interp.eval(&ast).unwrap().to_string(),
+        "[(\"r\", 2), (\"q\", 1)]"
+    );
+
+    let ast = "(x, [y]) := (1, [])".parse::<Ast>().unwrap();
+    assert_eq!(
+        interp.eval(&ast).unwrap_err(),
+        InterpreterError::new(
+            InterpreterErrorKind::DestructureFailed("(1, [])".to_string()),
+            Loc(0, 19)
+        )
+    );
+}
@@ -1,5 +1,8 @@
+mod env;
 mod error;
+#[allow(clippy::module_inception)]
 mod interp;
 
+pub use env::*;
 pub use error::*;
 pub use interp::*;
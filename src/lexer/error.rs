@@ -4,6 +4,11 @@ use crate::util::Annot;
 #[derive(Debug, PartialEq)]
 pub enum LexerErrorKind {
     InvalidChar(char),
+    InvalidNumber(String),
+    InvalidEscape(String),
+    UnterminatedString,
+    UnterminatedComment,
+    InconsistentIndent,
 }
 
 pub type LexerError = Annot<LexerErrorKind>;
@@ -12,15 +17,39 @@ impl LexerError {
     pub fn invalid_char(c: char, loc: Loc) -> Self {
         LexerError::new(LexerErrorKind::InvalidChar(c), loc)
     }
+
+    pub fn invalid_number(s: impl Into<String>, loc: Loc) -> Self {
+        LexerError::new(LexerErrorKind::InvalidNumber(s.into()), loc)
+    }
+
+    pub fn invalid_escape(s: impl Into<String>, loc: Loc) -> Self {
+        LexerError::new(LexerErrorKind::InvalidEscape(s.into()), loc)
+    }
+
+    pub fn unterminated_string(loc: Loc) -> Self {
+        LexerError::new(LexerErrorKind::UnterminatedString, loc)
+    }
+
+    pub fn unterminated_comment(loc: Loc) -> Self {
+        LexerError::new(LexerErrorKind::UnterminatedComment, loc)
+    }
+
+    pub fn inconsistent_indent(loc: Loc) -> Self {
+        LexerError::new(LexerErrorKind::InconsistentIndent, loc)
+    }
 }
 
 use std::fmt;
 impl fmt::Display for LexerError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use crate::lexer::LexerErrorKind::*;
-        let loc = &self.loc;
         match &self.value {
-            InvalidChar(c) => write!(f, "{}: invalid char '{}'", loc, c),
+            InvalidChar(c) => write!(f, "invalid char '{}'", c),
+            InvalidNumber(s) => write!(f, "invalid number literal {}", s),
+            InvalidEscape(s) => write!(f, "invalid escape sequence {}", s),
+            UnterminatedString => write!(f, "unterminated string literal"),
+            UnterminatedComment => write!(f, "unterminated block comment"),
+            InconsistentIndent => write!(f, "indentation does not match any outer block"),
         }
     }
 }
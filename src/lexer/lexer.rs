@@ -1,77 +1,367 @@
-use crate::lexer::{LexerError, Loc, Token};
-
-// fn recognize_many(input: &[u8], mut pos: usize, mut f: impl FnMut(u8) -> bool) -> usize {
-//     while pos < input.len() && f(input[pos]) {
-//         println!("pos: {:?}", pos);
-//         println!("len: {:?}", input.len());
-//         pos += 1;
-//     }
-//     pos
-// }
-
-pub fn lexer(input: &str) -> Result<Vec<Token>, LexerError> {
+use crate::lexer::{LexerError, Loc, Token, TokenKind};
+use crate::util::Integer;
+use unicode_xid::UnicodeXID;
+
+type Keyword = (&'static str, fn(Loc) -> Token);
+
+// words that cannot be used as identifiers
+const KEYWORDS: &[Keyword] = &[
+    ("fn", Token::fn_),
+    ("type", Token::type_),
+    ("match", Token::match_),
+    ("if", Token::if_),
+    ("then", Token::then),
+    ("else", Token::else_),
+    ("True", Token::true_),
+    ("False", Token::false_),
+];
+
+fn recognize_many(input: &[u8], mut pos: usize, mut f: impl FnMut(u8) -> bool) -> usize {
+    while pos < input.len() && f(input[pos]) {
+        pos += 1;
+    }
+    pos
+}
+
+// number ::= "0x" hex+ | "0o" oct+ | "0b" bin+ | dec+ ("." dec+)? (("e" | "E") ("+" | "-")? dec+)?
+// where "_" may appear between digits
+fn lex_number(input: &[u8], start: usize) -> Result<(Token, usize), LexerError> {
+    use std::str::from_utf8;
+    // the end of a run of digits, where each "_" must stand between two
+    let digits = |mut pos: usize, radix: u32| {
+        let is_digit = |pos: usize| input.get(pos).is_some_and(|&b| (b as char).is_digit(radix));
+        let first = pos;
+        while is_digit(pos) || (pos > first && input.get(pos) == Some(&b'_') && is_digit(pos + 1)) {
+            pos += 1;
+        }
+        pos
+    };
+    let text = |end: usize, skip: usize| {
+        from_utf8(&input[start + skip..end])
+            .unwrap()
+            .replace('_', "")
+    };
+    let invalid = |end: usize| {
+        let end = recognize_many(input, end, |b| b.is_ascii_alphanumeric() || b == b'_');
+        LexerError::invalid_number(from_utf8(&input[start..end]).unwrap(), Loc(start, end))
+    };
+
+    let radix = match input[start..] {
+        [b'0', b'x', ..] => Some(16),
+        [b'0', b'o', ..] => Some(8),
+        [b'0', b'b', ..] => Some(2),
+        _ => None,
+    };
+    let (token, end) = match radix {
+        Some(radix) => {
+            let end = digits(start + 2, radix);
+            let n = Integer::from_str_radix(&text(end, 2), radix).ok_or_else(|| invalid(end))?;
+            (Token::number(n, Loc(start, end)), end)
+        }
+        None => {
+            let mut end = digits(start, 10);
+            let mut is_float = false;
+            if input[end..].starts_with(b".") && input.get(end + 1).is_some_and(u8::is_ascii_digit)
+            {
+                end = digits(end + 1, 10);
+                is_float = true;
+            }
+            if let Some(b'e') | Some(b'E') = input.get(end) {
+                let exp = match input.get(end + 1) {
+                    Some(b'+') | Some(b'-') => end + 2,
+                    _ => end + 1,
+                };
+                if input.get(exp).is_some_and(u8::is_ascii_digit) {
+                    end = digits(exp, 10);
+                    is_float = true;
+                }
+            }
+            if is_float {
+                let f = text(end, 0).parse().unwrap();
+                (Token::float(f, Loc(start, end)), end)
+            } else {
+                let n = Integer::from_str_radix(&text(end, 0), 10).unwrap();
+                (Token::number(n, Loc(start, end)), end)
+            }
+        }
+    };
+    // a literal must not run into a name, as in `0xfg` or `1e`
+    match input.get(end) {
+        Some(b) if b.is_ascii_alphanumeric() || *b == b'_' => Err(invalid(end)),
+        _ => Ok((token, end)),
+    }
+}
+
+// ident ::= (xid_start | "_") (xid_continue | "'")*
+// where a name starting with an uppercase letter names a type or constructor
+fn lex_ident(input: &str, start: usize) -> (Token, usize) {
+    let len: usize = input[start..]
+        .chars()
+        .enumerate()
+        .take_while(|&(i, c)| match i {
+            0 => c.is_xid_start() || c == '_',
+            _ => c.is_xid_continue() || c == '\'',
+        })
+        .map(|(_, c)| c.len_utf8())
+        .sum();
+    let end = start + len;
+    let s = &input[start..end];
+    let loc = Loc(start, end);
+    let token = match KEYWORDS.iter().find(|(keyword, _)| *keyword == s) {
+        Some((_, keyword)) => keyword(loc),
+        None if s.starts_with(char::is_uppercase) => Token::upper(s, loc),
+        None => Token::var(s, loc),
+    };
+    (token, end)
+}
+
+// string ::= '"' (char | escape)* '"'
+// escape ::= "\\" ("n" | "t" | "r" | "0" | "\\" | '"') | "\\u{" hex+ "}"
+// a string cannot span lines, so a missing '"' is reported at the opening one
+fn lex_string(input: &[u8], start: usize) -> Result<(Token, usize), LexerError> {
+    let mut bytes = Vec::new();
+    let mut pos = start + 1;
+    loop {
+        match input.get(pos) {
+            Some(b'"') => break,
+            Some(b'\\') => {
+                let c = match input.get(pos + 1) {
+                    Some(b'n') => '\n',
+                    Some(b't') => '\t',
+                    Some(b'r') => '\r',
+                    Some(b'0') => '\0',
+                    Some(b'\\') => '\\',
+                    Some(b'"') => '"',
+                    Some(b'u') => {
+                        let (c, end) = lex_unicode_escape(input, pos)?;
+                        let mut buf = [0; 4];
+                        bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                        pos = end;
+                        continue;
+                    }
+                    Some(b'\n') | None => {
+                        return Err(LexerError::unterminated_string(Loc(start, start + 1)))
+                    }
+                    Some(_) => {
+                        let end = pos + 1 + utf8_len(input[pos + 1]);
+                        let s = std::str::from_utf8(&input[pos..end]).unwrap();
+                        return Err(LexerError::invalid_escape(s, Loc(pos, end)));
+                    }
+                };
+                bytes.push(c as u8);
+                pos += 2;
+            }
+            Some(b'\n') | None => {
+                return Err(LexerError::unterminated_string(Loc(start, start + 1)))
+            }
+            Some(&b) => {
+                bytes.push(b);
+                pos += 1;
+            }
+        }
+    }
+    // only whole chars were copied, since every split point is ASCII
+    let s = String::from_utf8(bytes).unwrap();
+    Ok((Token::str(s, Loc(start, pos + 1)), pos + 1))
+}
+
+// "\\u{" hex+ "}" starting at `start`
+fn lex_unicode_escape(input: &[u8], start: usize) -> Result<(char, usize), LexerError> {
+    use std::str::from_utf8;
+    let invalid = |end: usize| {
+        LexerError::invalid_escape(from_utf8(&input[start..end]).unwrap(), Loc(start, end))
+    };
+    if input.get(start + 2) != Some(&b'{') {
+        return Err(invalid(start + 2));
+    }
+    let digits = recognize_many(input, start + 3, |b| b.is_ascii_hexdigit());
+    if input.get(digits) != Some(&b'}') {
+        return Err(invalid(digits));
+    }
+    let hex = from_utf8(&input[start + 3..digits]).unwrap();
+    u32::from_str_radix(hex, 16)
+        .ok()
+        .and_then(std::char::from_u32)
+        .map(|c| (c, digits + 1))
+        .ok_or_else(|| invalid(digits + 1))
+}
+
+// length of the UTF-8 char starting with the byte `b`
+fn utf8_len(b: u8) -> usize {
+    match b {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        _ => 4,
+    }
+}
+
+// "/*" (block_comment | any)* "*/", where comments nest
+fn skip_block_comment(input: &[u8], start: usize) -> Result<usize, LexerError> {
+    let mut depth = 0;
+    let mut pos = start;
+    while pos < input.len() {
+        if input[pos..].starts_with(b"/*") {
+            depth += 1;
+            pos += 2;
+        } else if input[pos..].starts_with(b"*/") {
+            depth -= 1;
+            pos += 2;
+            if depth == 0 {
+                return Ok(pos);
+            }
+        } else {
+            pos += 1;
+        }
+    }
+    Err(LexerError::unterminated_comment(Loc(start, start + 2)))
+}
+
+// turns the indentation at the start of each line into tokens. A line
+// indented deeper than the enclosing block opens a new block with `Indent` in
+// place of its newline; a shallower one closes blocks with a `Dedent` each,
+// followed by the newline. Lines inside brackets continue the previous line.
+fn layout(input: &[u8], tokens: Vec<Token>) -> Result<Vec<Token>, LexerError> {
+    let mut out = Vec::new();
+    // the indentation of every open block, outermost first
+    let mut levels: Vec<&[u8]> = vec![b""];
+    let mut newlines = Vec::new();
+    let mut depth = 0usize;
+    for tok in tokens {
+        match tok.value {
+            TokenKind::Newline if depth > 0 => continue,
+            TokenKind::Newline => {
+                newlines.push(tok);
+                continue;
+            }
+            TokenKind::Lparen | TokenKind::Lbracket | TokenKind::Lbrace => depth += 1,
+            TokenKind::Rparen | TokenKind::Rbracket | TokenKind::Rbrace => {
+                depth = depth.saturating_sub(1)
+            }
+            _ => (),
+        }
+        if let Some(newline) = newlines.last() {
+            // the token may follow a block comment on its line
+            let (line_start, start) = (newline.loc.1, tok.loc.0);
+            let indent_end = recognize_many(input, line_start, |b| b == b' ' || b == b'\t');
+            let indent = &input[line_start..indent_end];
+            let top = levels[levels.len() - 1];
+            if indent.len() > top.len() && indent.starts_with(top) {
+                levels.push(indent);
+                out.push(Token::indent(Loc(line_start, indent_end)));
+                newlines.clear();
+            } else {
+                while levels[levels.len() - 1].len() > indent.len() {
+                    levels.pop();
+                    out.push(Token::dedent(Loc(start, start)));
+                }
+                if levels[levels.len() - 1] != indent {
+                    return Err(LexerError::inconsistent_indent(Loc(line_start, indent_end)));
+                }
+                out.append(&mut newlines);
+            }
+        }
+        out.push(tok);
+    }
+    for _ in 1..levels.len() {
+        out.push(Token::dedent(Loc(input.len(), input.len())));
+    }
+    out.append(&mut newlines);
+    Ok(out)
+}
+
+pub fn lexer(src: &str) -> Result<Vec<Token>, LexerError> {
     let mut tokens = Vec::new();
-    let input = input.as_bytes();
+    let input = src.as_bytes();
     let mut pos = 0;
     macro_rules! lex_a_token {
-        ($token_method:ident, $pos:ident) => {{
-            tokens.push(Token::$token_method(Loc(pos, pos + 1)));
-            pos = $pos + 1;
+        ($token_method:ident) => {
+            lex_a_token!($token_method, 1)
+        };
+        ($token_method:ident, $len:expr) => {{
+            tokens.push(Token::$token_method(Loc(pos, pos + $len)));
+            pos += $len;
         }};
     }
 
     while pos < input.len() {
         match input[pos] {
-            b'0'...b'9' => {
-                // TODO: clean
-                use std::str::from_utf8;
-                let start = pos;
-                while pos < input.len() && b"1234567890".contains(&input[pos]) {
-                    pos += 1;
-                }
-                let n = from_utf8(&input[start..pos]).unwrap().parse().unwrap();
-                tokens.push(Token::number(n, Loc(start, pos)));
-                // let end = recognize_many(input, pos, |b| b"0123456789".contains(&b));
-                // let n = from_utf8(&input[start..end]).unwrap().parse().unwrap();
-                // tokens.push(Token::number(n, Loc(start, end)));
-            }
-            b'a'...b'z' => {
-                // TODO: clean
-                use std::str::from_utf8;
-                let start = pos;
-                // let end = recognize_many(input, start, |b| b"abcdefghijklmnopqrstuvwxyz".contains(&b));
-                // let s = from_utf8(&input[start..end]).unwrap();
-                // tokens.push(Token::var(s, Loc(start, end)));
-                while pos < input.len() && b"abcdefghijklmnopqrstuvwxyz".contains(&input[pos]) {
-                    pos += 1;
-                }
-                let s = from_utf8(&input[start..pos]).unwrap();
-                tokens.push(Token::var(s, Loc(start, pos)));
-            }
-            b'+' => lex_a_token!(plus, pos),
-            b'-' => lex_a_token!(minus, pos),
-            b'*' => lex_a_token!(asterisk, pos),
-            b'/' => lex_a_token!(slash, pos),
-            b'(' => lex_a_token!(lparen, pos),
-            b')' => lex_a_token!(rparen, pos),
-            b':' => {
-                // TODO: clean
-                let start = pos;
-                // let end = recognize_many(input, start, |b| b"=".contains(&b));
-                // tokens.push(Token::bind(Loc(start, end)));
-                // FIXME:
-                while pos < input.len() && b":=".contains(&input[pos]) {
-                    pos += 1;
-                }
-                tokens.push(Token::bind(Loc(start, pos)));
+            b'0'..=b'9' => {
+                let (token, end) = lex_number(input, pos)?;
+                tokens.push(token);
+                pos = end;
+            }
+            b'"' => {
+                let (token, end) = lex_string(input, pos)?;
+                tokens.push(token);
+                pos = end;
             }
-            b' ' | b'\n' | b'\t' => {
-                pos = pos + 1;
+            b'+' if input[pos..].starts_with(b"++") => lex_a_token!(plus_plus, 2),
+            b'+' => lex_a_token!(plus),
+            b'-' if input[pos..].starts_with(b"->") => lex_a_token!(arrow, 2),
+            b'-' => lex_a_token!(minus),
+            b'*' => lex_a_token!(asterisk),
+            b'/' if input[pos..].starts_with(b"///") && !input[pos..].starts_with(b"////") => {
+                let end = recognize_many(input, pos, |b| b != b'\n');
+                let text = std::str::from_utf8(&input[pos + 3..end]).unwrap();
+                let text = text.trim_end_matches('\r');
+                let text = text.strip_prefix(' ').unwrap_or(text);
+                tokens.push(Token::doc_comment(text, Loc(pos, end)));
+                pos = end;
+            }
+            b'/' if input[pos..].starts_with(b"//") => {
+                pos = recognize_many(input, pos, |b| b != b'\n');
+            }
+            b'/' if input[pos..].starts_with(b"/*") => {
+                pos = skip_block_comment(input, pos)?;
+            }
+            b'/' => lex_a_token!(slash),
+            b'%' => lex_a_token!(percent),
+            b'^' => lex_a_token!(caret),
+            b'(' => lex_a_token!(lparen),
+            b')' => lex_a_token!(rparen),
+            b'[' => lex_a_token!(lbracket),
+            b']' => lex_a_token!(rbracket),
+            b'{' => lex_a_token!(lbrace),
+            b'}' => lex_a_token!(rbrace),
+            b',' => lex_a_token!(comma),
+            b'.' => lex_a_token!(dot),
+            b':' if input[pos..].starts_with(b"::=") => lex_a_token!(type_bind, 3),
+            b':' if input[pos..].starts_with(b":=") => lex_a_token!(bind, 2),
+            b':' if input[pos..].starts_with(b"::") => lex_a_token!(colon_colon, 2),
+            b':' => lex_a_token!(colon),
+            b'=' if input[pos..].starts_with(b"=>") => lex_a_token!(fat_arrow, 2),
+            b'=' if input[pos..].starts_with(b"==") => lex_a_token!(eq_eq, 2),
+            b'=' => lex_a_token!(equal),
+            b'!' if input[pos..].starts_with(b"!=") => lex_a_token!(not_eq, 2),
+            b'!' => lex_a_token!(bang),
+            b'<' if input[pos..].starts_with(b"<=") => lex_a_token!(lt_eq, 2),
+            b'<' => lex_a_token!(lt),
+            b'>' if input[pos..].starts_with(b">=") => lex_a_token!(gt_eq, 2),
+            b'>' => lex_a_token!(gt),
+            b'&' if input[pos..].starts_with(b"&&") => lex_a_token!(and_and, 2),
+            b'|' if input[pos..].starts_with(b"||") => lex_a_token!(or_or, 2),
+            b'|' => lex_a_token!(bar),
+            b'_' if !src[pos + 1..].starts_with(UnicodeXID::is_xid_continue) => {
+                lex_a_token!(underscore)
+            }
+            b'\n' => lex_a_token!(newline),
+            b' ' | b'\r' | b'\t' => {
+                pos += 1;
+            }
+            _ => {
+                let c = src[pos..].chars().next().unwrap();
+                if !(c.is_xid_start() || c == '_') {
+                    let loc = Loc(pos, pos + c.len_utf8());
+                    return Err(LexerError::invalid_char(c, loc));
+                }
+                let (token, end) = lex_ident(src, pos);
+                tokens.push(token);
+                pos = end;
             }
-            b => return Err(LexerError::invalid_char(b as char, Loc(pos, pos + 1))),
         }
     }
-    Ok(tokens)
+    layout(input, tokens)
 }
 
 #[test]
@@ -105,3 +395,353 @@ fn test_bind_lexer() {
         ])
     )
 }
+
+#[test]
+fn test_newline_lexer() {
+    assert_eq!(
+        lexer("1\n\nx"),
+        Ok(vec![
+            Token::number(1, Loc(0, 1)),
+            Token::newline(Loc(1, 2)),
+            Token::newline(Loc(2, 3)),
+            Token::var("x", Loc(3, 4)),
+        ])
+    )
+}
+
+#[test]
+fn test_type_bind_lexer() {
+    assert_eq!(
+        lexer("mod ::= Int [Int] -> Int"),
+        Ok(vec![
+            Token::var("mod", Loc(0, 3)),
+            Token::type_bind(Loc(4, 7)),
+            Token::upper("Int", Loc(8, 11)),
+            Token::lbracket(Loc(12, 13)),
+            Token::upper("Int", Loc(13, 16)),
+            Token::rbracket(Loc(16, 17)),
+            Token::arrow(Loc(18, 20)),
+            Token::upper("Int", Loc(21, 24)),
+        ])
+    )
+}
+
+#[test]
+fn test_lambda_lexer() {
+    assert_eq!(
+        lexer("x=>f x"),
+        Ok(vec![
+            Token::var("x", Loc(0, 1)),
+            Token::fat_arrow(Loc(1, 3)),
+            Token::var("f", Loc(3, 4)),
+            Token::var("x", Loc(5, 6)),
+        ])
+    )
+}
+
+#[test]
+fn test_mod_pow_lexer() {
+    assert_eq!(
+        lexer("9 % 3 ^ 2"),
+        Ok(vec![
+            Token::number(9, Loc(0, 1)),
+            Token::percent(Loc(2, 3)),
+            Token::number(3, Loc(4, 5)),
+            Token::caret(Loc(6, 7)),
+            Token::number(2, Loc(8, 9)),
+        ])
+    )
+}
+
+#[test]
+fn test_big_number_lexer() {
+    assert_eq!(
+        lexer("1 + 99999999999999999999"),
+        Ok(vec![
+            Token::number(1, Loc(0, 1)),
+            Token::plus(Loc(2, 3)),
+            Token::number(
+                Integer::from_str_radix("99999999999999999999", 10).unwrap(),
+                Loc(4, 24)
+            ),
+        ])
+    )
+}
+
+#[test]
+fn test_number_literal_lexer() {
+    assert_eq!(
+        lexer("2.5 1e-9 2E3 0xff 0o17 0b1010 1_000_000"),
+        Ok(vec![
+            Token::float(2.5, Loc(0, 3)),
+            Token::float(1e-9, Loc(4, 8)),
+            Token::float(2000.0, Loc(9, 12)),
+            Token::number(255, Loc(13, 17)),
+            Token::number(15, Loc(18, 22)),
+            Token::number(10, Loc(23, 29)),
+            Token::number(1_000_000, Loc(30, 39)),
+        ])
+    );
+    assert_eq!(
+        lexer("1 + 0xfg"),
+        Err(LexerError::invalid_number("0xfg", Loc(4, 8)))
+    );
+    assert_eq!(
+        lexer("1e"),
+        Err(LexerError::invalid_number("1e", Loc(0, 2)))
+    );
+    assert_eq!(
+        lexer("0b"),
+        Err(LexerError::invalid_number("0b", Loc(0, 2)))
+    );
+    assert_eq!(
+        lexer("1_2.3_4e1_0"),
+        Ok(vec![Token::float(12.34e10, Loc(0, 11))])
+    );
+    for src in &["1_", "1__0", "0x_ff", "1e_5"] {
+        assert_eq!(
+            lexer(src),
+            Err(LexerError::invalid_number(*src, Loc(0, src.len())))
+        );
+    }
+}
+
+#[test]
+fn test_string_lexer() {
+    assert_eq!(
+        lexer(r#""fizz" ++ "a\tb\n\"\\\u{3042}" ++ "あ""#),
+        Ok(vec![
+            Token::str("fizz", Loc(0, 6)),
+            Token::plus_plus(Loc(7, 9)),
+            Token::str("a\tb\n\"\\あ", Loc(10, 30)),
+            Token::plus_plus(Loc(31, 33)),
+            Token::str("あ", Loc(34, 39)),
+        ])
+    );
+    assert_eq!(
+        lexer("x := \"fizz\nbuzz\""),
+        Err(LexerError::unterminated_string(Loc(5, 6)))
+    );
+    assert_eq!(
+        lexer("\"fizz\\\""),
+        Err(LexerError::unterminated_string(Loc(0, 1)))
+    );
+    assert_eq!(
+        lexer(r#""a\qb""#),
+        Err(LexerError::invalid_escape("\\q", Loc(2, 4)))
+    );
+    assert_eq!(
+        lexer(r#""\u{110000}""#),
+        Err(LexerError::invalid_escape("\\u{110000}", Loc(1, 11)))
+    );
+}
+
+#[test]
+fn test_match_lexer() {
+    assert_eq!(
+        lexer("match n | 0 => x | _ => matches"),
+        Ok(vec![
+            Token::match_(Loc(0, 5)),
+            Token::var("n", Loc(6, 7)),
+            Token::bar(Loc(8, 9)),
+            Token::number(0, Loc(10, 11)),
+            Token::fat_arrow(Loc(12, 14)),
+            Token::var("x", Loc(15, 16)),
+            Token::bar(Loc(17, 18)),
+            Token::underscore(Loc(19, 20)),
+            Token::fat_arrow(Loc(21, 23)),
+            Token::var("matches", Loc(24, 31)),
+        ])
+    )
+}
+
+#[test]
+fn test_bool_lexer() {
+    assert_eq!(
+        lexer("if !(a<=b) || a==b && a!=b then True else a>=b|a<b>c"),
+        Ok(vec![
+            Token::if_(Loc(0, 2)),
+            Token::bang(Loc(3, 4)),
+            Token::lparen(Loc(4, 5)),
+            Token::var("a", Loc(5, 6)),
+            Token::lt_eq(Loc(6, 8)),
+            Token::var("b", Loc(8, 9)),
+            Token::rparen(Loc(9, 10)),
+            Token::or_or(Loc(11, 13)),
+            Token::var("a", Loc(14, 15)),
+            Token::eq_eq(Loc(15, 17)),
+            Token::var("b", Loc(17, 18)),
+            Token::and_and(Loc(19, 21)),
+            Token::var("a", Loc(22, 23)),
+            Token::not_eq(Loc(23, 25)),
+            Token::var("b", Loc(25, 26)),
+            Token::then(Loc(27, 31)),
+            Token::true_(Loc(32, 36)),
+            Token::else_(Loc(37, 41)),
+            Token::var("a", Loc(42, 43)),
+            Token::gt_eq(Loc(43, 45)),
+            Token::var("b", Loc(45, 46)),
+            Token::bar(Loc(46, 47)),
+            Token::var("a", Loc(47, 48)),
+            Token::lt(Loc(48, 49)),
+            Token::var("b", Loc(49, 50)),
+            Token::gt(Loc(50, 51)),
+            Token::var("c", Loc(51, 52)),
+        ])
+    );
+    assert_eq!(
+        lexer("a & b"),
+        Err(LexerError::invalid_char('&', Loc(2, 3)))
+    );
+}
+
+#[test]
+fn test_layout_lexer() {
+    assert_eq!(
+        lexer("f := n =>\n  match n\n    0 => (1\n  + 2)\n\n    m => m\nf 1\n"),
+        Ok(vec![
+            Token::var("f", Loc(0, 1)),
+            Token::bind(Loc(2, 4)),
+            Token::var("n", Loc(5, 6)),
+            Token::fat_arrow(Loc(7, 9)),
+            Token::indent(Loc(10, 12)),
+            Token::match_(Loc(12, 17)),
+            Token::var("n", Loc(18, 19)),
+            Token::indent(Loc(20, 24)),
+            Token::number(0, Loc(24, 25)),
+            Token::fat_arrow(Loc(26, 28)),
+            Token::lparen(Loc(29, 30)),
+            Token::number(1, Loc(30, 31)),
+            Token::plus(Loc(34, 35)),
+            Token::number(2, Loc(36, 37)),
+            Token::rparen(Loc(37, 38)),
+            Token::newline(Loc(38, 39)),
+            Token::newline(Loc(39, 40)),
+            Token::var("m", Loc(44, 45)),
+            Token::fat_arrow(Loc(46, 48)),
+            Token::var("m", Loc(49, 50)),
+            Token::dedent(Loc(51, 51)),
+            Token::dedent(Loc(51, 51)),
+            Token::newline(Loc(50, 51)),
+            Token::var("f", Loc(51, 52)),
+            Token::number(1, Loc(53, 54)),
+            Token::newline(Loc(54, 55)),
+        ])
+    );
+    assert_eq!(
+        lexer("x :=\n    1\n  2"),
+        Err(LexerError::inconsistent_indent(Loc(11, 13)))
+    );
+    assert_eq!(
+        lexer("x :=\n\t1\n  2"),
+        Err(LexerError::inconsistent_indent(Loc(8, 10)))
+    );
+}
+
+#[test]
+fn test_comment_lexer() {
+    assert_eq!(
+        lexer("/// 割る\n///  two\ndiv := 4 / /* 0 /* nested */ */ 2 // 無名関数\n//// x"),
+        Ok(vec![
+            Token::doc_comment("割る", Loc(0, 10)),
+            Token::newline(Loc(10, 11)),
+            Token::doc_comment(" two", Loc(11, 19)),
+            Token::newline(Loc(19, 20)),
+            Token::var("div", Loc(20, 23)),
+            Token::bind(Loc(24, 26)),
+            Token::number(4, Loc(27, 28)),
+            Token::slash(Loc(29, 30)),
+            Token::number(2, Loc(52, 53)),
+            Token::newline(Loc(69, 70)),
+        ])
+    );
+    assert_eq!(
+        lexer("f := x =>\n  /* a\n  b */ x"),
+        Ok(vec![
+            Token::var("f", Loc(0, 1)),
+            Token::bind(Loc(2, 4)),
+            Token::var("x", Loc(5, 6)),
+            Token::fat_arrow(Loc(7, 9)),
+            Token::indent(Loc(10, 12)),
+            Token::var("x", Loc(24, 25)),
+            Token::dedent(Loc(25, 25)),
+        ])
+    );
+    assert_eq!(
+        lexer("1 /* /* */ 2"),
+        Err(LexerError::unterminated_comment(Loc(2, 4)))
+    );
+}
+
+#[test]
+fn test_ident_lexer() {
+    assert_eq!(
+        lexer("is_op x1 x' _tmp _ String 割る数 Ünïcode fn typed"),
+        Ok(vec![
+            Token::var("is_op", Loc(0, 5)),
+            Token::var("x1", Loc(6, 8)),
+            Token::var("x'", Loc(9, 11)),
+            Token::var("_tmp", Loc(12, 16)),
+            Token::underscore(Loc(17, 18)),
+            Token::upper("String", Loc(19, 25)),
+            Token::var("割る数", Loc(26, 35)),
+            Token::upper("Ünïcode", Loc(36, 45)),
+            Token::fn_(Loc(46, 48)),
+            Token::var("typed", Loc(49, 54)),
+        ])
+    );
+    assert_eq!(
+        lexer("x := 1 → 2"),
+        Err(LexerError::invalid_char('→', Loc(7, 10)))
+    );
+}
+
+#[test]
+fn test_list_lexer() {
+    assert_eq!(
+        lexer("x :: xs[0] ++ [1, 2]"),
+        Ok(vec![
+            Token::var("x", Loc(0, 1)),
+            Token::colon_colon(Loc(2, 4)),
+            Token::var("xs", Loc(5, 7)),
+            Token::lbracket(Loc(7, 8)),
+            Token::number(0, Loc(8, 9)),
+            Token::rbracket(Loc(9, 10)),
+            Token::plus_plus(Loc(11, 13)),
+            Token::lbracket(Loc(14, 15)),
+            Token::number(1, Loc(15, 16)),
+            Token::comma(Loc(16, 17)),
+            Token::number(2, Loc(18, 19)),
+            Token::rbracket(Loc(19, 20)),
+        ])
+    );
+}
+
+#[test]
+fn test_record_lexer() {
+    // a newline inside braces continues the line
+    assert_eq!(
+        lexer("{ a = 1.5,\n  b = r.b }"),
+        Ok(vec![
+            Token::lbrace(Loc(0, 1)),
+            Token::var("a", Loc(2, 3)),
+            Token::equal(Loc(4, 5)),
+            Token::float(1.5, Loc(6, 9)),
+            Token::comma(Loc(9, 10)),
+            Token::var("b", Loc(13, 14)),
+            Token::equal(Loc(15, 16)),
+            Token::var("r", Loc(17, 18)),
+            Token::dot(Loc(18, 19)),
+            Token::var("b", Loc(19, 20)),
+            Token::rbrace(Loc(21, 22)),
+        ])
+    );
+    assert_eq!(
+        lexer("a : Int"),
+        Ok(vec![
+            Token::var("a", Loc(0, 1)),
+            Token::colon(Loc(2, 3)),
+            Token::upper("Int", Loc(4, 7)),
+        ])
+    );
+}
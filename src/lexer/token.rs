@@ -1,18 +1,59 @@
 use crate::lexer::Loc;
-use crate::util::Annot;
+use crate::util::{Annot, Integer};
 use std::fmt;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenKind {
-    Number(i32), // 0..9
-    Plus,        // +
-    Minus,       // -
-    Asterisk,    // *
-    Slash,       // /
-    Lparen,      // (
-    Rparen,      // )
-    Bind,        // :=
-    Var(String), // hoge
+    Number(Integer),    // 0..9
+    Float(f64),         // 2.5
+    Str(String),        // "hoge"
+    Plus,               // +
+    PlusPlus,           // ++
+    Minus,              // -
+    Asterisk,           // *
+    Slash,              // /
+    Percent,            // %
+    Caret,              // ^
+    Equal,              // =
+    EqEq,               // ==
+    NotEq,              // !=
+    Lt,                 // <
+    LtEq,               // <=
+    Gt,                 // >
+    GtEq,               // >=
+    AndAnd,             // &&
+    OrOr,               // ||
+    Bang,               // !
+    Lparen,             // (
+    Rparen,             // )
+    Lbracket,           // [
+    Rbracket,           // ]
+    Lbrace,             // {
+    Rbrace,             // }
+    Comma,              // ,
+    Dot,                // .
+    Colon,              // :
+    Bind,               // :=
+    TypeBind,           // ::=
+    ColonColon,         // ::
+    Arrow,              // ->
+    FatArrow,           // =>
+    Bar,                // |
+    Underscore,         // _
+    Fn,                 // fn
+    Type,               // type
+    Match,              // match
+    If,                 // if
+    Then,               // then
+    Else,               // else
+    True,               // True
+    False,              // False
+    Var(String),        // hoge
+    Upper(String),      // Hoge
+    Newline,            // \n
+    DocComment(String), // /// hoge
+    Indent,             // start of a more indented line
+    Dedent,             // end of an indented block
 }
 
 impl fmt::Display for TokenKind {
@@ -20,14 +61,54 @@ impl fmt::Display for TokenKind {
         use self::TokenKind::*;
         match self {
             Number(n) => n.fmt(f),
+            Float(n) => write!(f, "{:?}", n),
+            Str(s) => write!(f, "{:?}", s),
             Plus => write!(f, "+"),
+            PlusPlus => write!(f, "++"),
             Minus => write!(f, "-"),
             Asterisk => write!(f, "*"),
             Slash => write!(f, "/"),
+            Percent => write!(f, "%"),
+            Caret => write!(f, "^"),
+            Equal => write!(f, "="),
+            EqEq => write!(f, "=="),
+            NotEq => write!(f, "!="),
+            Lt => write!(f, "<"),
+            LtEq => write!(f, "<="),
+            Gt => write!(f, ">"),
+            GtEq => write!(f, ">="),
+            AndAnd => write!(f, "&&"),
+            OrOr => write!(f, "||"),
+            Bang => write!(f, "!"),
             Lparen => write!(f, "("),
             Rparen => write!(f, ")"),
+            Lbracket => write!(f, "["),
+            Rbracket => write!(f, "]"),
+            Lbrace => write!(f, "{{"),
+            Rbrace => write!(f, "}}"),
+            Comma => write!(f, ","),
+            Dot => write!(f, "."),
+            Colon => write!(f, ":"),
             Bind => write!(f, ":="),
-            Var(s) => s.fmt(f),
+            TypeBind => write!(f, "::="),
+            ColonColon => write!(f, "::"),
+            Arrow => write!(f, "->"),
+            FatArrow => write!(f, "=>"),
+            Bar => write!(f, "|"),
+            Underscore => write!(f, "_"),
+            Fn => write!(f, "fn"),
+            Type => write!(f, "type"),
+            Match => write!(f, "match"),
+            If => write!(f, "if"),
+            Then => write!(f, "then"),
+            Else => write!(f, "else"),
+            True => write!(f, "True"),
+            False => write!(f, "False"),
+            Var(s) | Upper(s) => s.fmt(f),
+            Newline => write!(f, "newline"),
+            DocComment(s) => write!(f, "///{}", s),
+            Indent => write!(f, "indent"),
+            Dedent => write!(f, "dedent"),
         }
     }
 }
@@ -35,14 +116,26 @@ impl fmt::Display for TokenKind {
 pub type Token = Annot<TokenKind>;
 
 impl Token {
-    pub fn number(n: i32, loc: Loc) -> Self {
-        Self::new(TokenKind::Number(n), loc)
+    pub fn number(n: impl Into<Integer>, loc: Loc) -> Self {
+        Self::new(TokenKind::Number(n.into()), loc)
+    }
+
+    pub fn float(n: f64, loc: Loc) -> Self {
+        Self::new(TokenKind::Float(n), loc)
+    }
+
+    pub fn str(s: impl Into<String>, loc: Loc) -> Self {
+        Self::new(TokenKind::Str(s.into()), loc)
     }
 
     pub fn plus(loc: Loc) -> Self {
         Self::new(TokenKind::Plus, loc)
     }
 
+    pub fn plus_plus(loc: Loc) -> Self {
+        Self::new(TokenKind::PlusPlus, loc)
+    }
+
     pub fn minus(loc: Loc) -> Self {
         Self::new(TokenKind::Minus, loc)
     }
@@ -55,6 +148,54 @@ impl Token {
         Self::new(TokenKind::Slash, loc)
     }
 
+    pub fn percent(loc: Loc) -> Self {
+        Self::new(TokenKind::Percent, loc)
+    }
+
+    pub fn caret(loc: Loc) -> Self {
+        Self::new(TokenKind::Caret, loc)
+    }
+
+    pub fn equal(loc: Loc) -> Self {
+        Self::new(TokenKind::Equal, loc)
+    }
+
+    pub fn eq_eq(loc: Loc) -> Self {
+        Self::new(TokenKind::EqEq, loc)
+    }
+
+    pub fn not_eq(loc: Loc) -> Self {
+        Self::new(TokenKind::NotEq, loc)
+    }
+
+    pub fn lt(loc: Loc) -> Self {
+        Self::new(TokenKind::Lt, loc)
+    }
+
+    pub fn lt_eq(loc: Loc) -> Self {
+        Self::new(TokenKind::LtEq, loc)
+    }
+
+    pub fn gt(loc: Loc) -> Self {
+        Self::new(TokenKind::Gt, loc)
+    }
+
+    pub fn gt_eq(loc: Loc) -> Self {
+        Self::new(TokenKind::GtEq, loc)
+    }
+
+    pub fn and_and(loc: Loc) -> Self {
+        Self::new(TokenKind::AndAnd, loc)
+    }
+
+    pub fn or_or(loc: Loc) -> Self {
+        Self::new(TokenKind::OrOr, loc)
+    }
+
+    pub fn bang(loc: Loc) -> Self {
+        Self::new(TokenKind::Bang, loc)
+    }
+
     pub fn lparen(loc: Loc) -> Self {
         Self::new(TokenKind::Lparen, loc)
     }
@@ -63,11 +204,115 @@ impl Token {
         Self::new(TokenKind::Rparen, loc)
     }
 
+    pub fn lbracket(loc: Loc) -> Self {
+        Self::new(TokenKind::Lbracket, loc)
+    }
+
+    pub fn rbracket(loc: Loc) -> Self {
+        Self::new(TokenKind::Rbracket, loc)
+    }
+
+    pub fn lbrace(loc: Loc) -> Self {
+        Self::new(TokenKind::Lbrace, loc)
+    }
+
+    pub fn rbrace(loc: Loc) -> Self {
+        Self::new(TokenKind::Rbrace, loc)
+    }
+
+    pub fn comma(loc: Loc) -> Self {
+        Self::new(TokenKind::Comma, loc)
+    }
+
+    pub fn dot(loc: Loc) -> Self {
+        Self::new(TokenKind::Dot, loc)
+    }
+
+    pub fn colon(loc: Loc) -> Self {
+        Self::new(TokenKind::Colon, loc)
+    }
+
     pub fn bind(loc: Loc) -> Self {
         Self::new(TokenKind::Bind, loc)
     }
 
+    pub fn type_bind(loc: Loc) -> Self {
+        Self::new(TokenKind::TypeBind, loc)
+    }
+
+    pub fn colon_colon(loc: Loc) -> Self {
+        Self::new(TokenKind::ColonColon, loc)
+    }
+
+    pub fn arrow(loc: Loc) -> Self {
+        Self::new(TokenKind::Arrow, loc)
+    }
+
+    pub fn fat_arrow(loc: Loc) -> Self {
+        Self::new(TokenKind::FatArrow, loc)
+    }
+
+    pub fn bar(loc: Loc) -> Self {
+        Self::new(TokenKind::Bar, loc)
+    }
+
+    pub fn underscore(loc: Loc) -> Self {
+        Self::new(TokenKind::Underscore, loc)
+    }
+
+    pub fn fn_(loc: Loc) -> Self {
+        Self::new(TokenKind::Fn, loc)
+    }
+
+    pub fn type_(loc: Loc) -> Self {
+        Self::new(TokenKind::Type, loc)
+    }
+
+    pub fn match_(loc: Loc) -> Self {
+        Self::new(TokenKind::Match, loc)
+    }
+
+    pub fn if_(loc: Loc) -> Self {
+        Self::new(TokenKind::If, loc)
+    }
+
+    pub fn then(loc: Loc) -> Self {
+        Self::new(TokenKind::Then, loc)
+    }
+
+    pub fn else_(loc: Loc) -> Self {
+        Self::new(TokenKind::Else, loc)
+    }
+
+    pub fn true_(loc: Loc) -> Self {
+        Self::new(TokenKind::True, loc)
+    }
+
+    pub fn false_(loc: Loc) -> Self {
+        Self::new(TokenKind::False, loc)
+    }
+
     pub fn var(s: impl Into<String>, loc: Loc) -> Self {
         Self::new(TokenKind::Var(s.into()), loc)
     }
+
+    pub fn upper(s: impl Into<String>, loc: Loc) -> Self {
+        Self::new(TokenKind::Upper(s.into()), loc)
+    }
+
+    pub fn newline(loc: Loc) -> Self {
+        Self::new(TokenKind::Newline, loc)
+    }
+
+    pub fn doc_comment(s: impl Into<String>, loc: Loc) -> Self {
+        Self::new(TokenKind::DocComment(s.into()), loc)
+    }
+
+    pub fn indent(loc: Loc) -> Self {
+        Self::new(TokenKind::Indent, loc)
+    }
+
+    pub fn dedent(loc: Loc) -> Self {
+        Self::new(TokenKind::Dedent, loc)
+    }
 }
@@ -2,15 +2,20 @@ mod error;
 mod interp;
 mod lexer;
 mod parser;
+mod typeck;
 mod util;
 use std::io;
+use std::{env, fs, panic, process, thread};
+
+const USAGE: &str = "usage: rytl [repl | run <file> | tokens [file] | ast [file] | docs <file>]";
+const REPL: &str = "<stdin>";
 
 fn prompt(s: &str) -> io::Result<()> {
     use std::io::{stdout, Write};
 
     let stdout = stdout();
     let mut stdout = stdout.lock();
-    stdout.write(s.as_bytes())?;
+    stdout.write_all(s.as_bytes())?;
     stdout.flush()
 }
 
@@ -27,6 +32,8 @@ fn run_lexer() {
             let token = match lexer::lexer(&line) {
                 Ok(token) => token,
                 Err(e) => {
+                    let e = error::Error::from(e);
+                    e.show_diagnostic(&error::SourceMap::new(REPL, &line));
                     error::show_trace(e);
                     continue;
                 }
@@ -52,7 +59,7 @@ fn run_parser() {
             let ast = match line.parse::<parser::Ast>() {
                 Ok(ast) => ast,
                 Err(e) => {
-                    e.show_diagnostic(&line);
+                    e.show_diagnostic(&error::SourceMap::new(REPL, &line));
                     error::show_trace(e);
                     continue;
                 }
@@ -66,6 +73,7 @@ fn run_parser() {
 
 fn run_eval() {
     use std::io::{self, BufRead, BufReader};
+    let mut checker = typeck::TypeChecker::new();
     let mut interp = interp::Interpreter::new();
 
     let stdin = io::stdin();
@@ -79,28 +87,197 @@ fn run_eval() {
             let ast = match line.parse::<parser::Ast>() {
                 Ok(ast) => ast,
                 Err(e) => {
-                    e.show_diagnostic(&line);
+                    e.show_diagnostic(&error::SourceMap::new(REPL, &line));
                     error::show_trace(e);
                     continue;
                 }
             };
+            checker.new_source();
+            let checked = checker.check(&ast);
+            for w in checker.take_warnings() {
+                w.show_diagnostic(&error::SourceMap::new(REPL, &line));
+            }
+            if let Err(e) = checked {
+                e.show_diagnostic(&error::SourceMap::new(REPL, &line));
+                error::show_trace(e);
+                continue;
+            }
             let n = match interp.eval(&ast) {
                 Ok(n) => n,
                 Err(e) => {
-                    e.show_diagnostic(&line);
+                    e.show_diagnostic(&error::SourceMap::new(REPL, &line));
                     error::show_trace(e);
                     continue;
                 }
             };
-            println!("{}", n);
+            // a binding shows the type inferred for it instead of `()`
+            for var in bound_vars(&ast) {
+                if let Some(scheme) = checker.scheme_of(var) {
+                    println!("{} : {}", var, scheme);
+                }
+            }
+            match n {
+                interp::Value::Void => (),
+                n => println!("{}", n),
+            }
         } else {
             break;
         }
     }
 }
 
+// the variables bound by the statements of a program
+fn bound_vars(ast: &parser::Ast) -> Vec<&str> {
+    use parser::AstKind::{Bind, Destructure, Program};
+    match ast.value {
+        Program(ref stmts) => stmts
+            .iter()
+            .flat_map(|stmt| match stmt.value {
+                Bind { ref var, .. } => vec![var.as_str()],
+                Destructure { ref pat, .. } => pat.vars(),
+                _ => Vec::new(),
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn read_source(path: &str) -> Result<String, ()> {
+    fs::read_to_string(path).map_err(|e| eprintln!("cannot read {}: {}", path, e))
+}
+
+fn lex_source(src: &error::SourceMap) -> Result<(), ()> {
+    let tokens = lexer::lexer(src.src()).map_err(|e| {
+        let e = error::Error::from(e);
+        e.show_diagnostic(src);
+        error::show_trace(e);
+    })?;
+    for tok in tokens {
+        println!("{}\t{:?}", tok.loc, tok.value);
+    }
+    Ok(())
+}
+
+fn parse_source(src: &error::SourceMap) -> Result<parser::Ast, ()> {
+    src.src().parse::<parser::Ast>().map_err(|e| {
+        e.show_diagnostic(src);
+        error::show_trace(e);
+    })
+}
+
+// prints every binding and type declaration that has a doc comment
+fn doc_source(src: &error::SourceMap) -> Result<(), ()> {
+    use parser::AstKind::{Bind, Data, Program, TypeAlias, TypeDecl};
+    let stmts = match parse_source(src)?.value {
+        Program(stmts) => stmts,
+        _ => unreachable!(),
+    };
+    for stmt in stmts {
+        let text = &src.src()[stmt.loc.0..stmt.loc.1];
+        let (head, doc) = match stmt.value {
+            Bind {
+                var,
+                doc: Some(doc),
+                ..
+            } => (format!("{} :=", var), doc),
+            TypeDecl {
+                var,
+                ty,
+                doc: Some(doc),
+            } => (
+                format!("{} ::= {}", var, &src.src()[ty.loc.0..ty.loc.1]),
+                doc,
+            ),
+            // a type definition is shown as written
+            TypeAlias { doc: Some(doc), .. } | Data { doc: Some(doc), .. } => {
+                (text.to_string(), doc)
+            }
+            _ => continue,
+        };
+        println!("{}", head);
+        for line in doc.lines() {
+            println!("    {}", line);
+        }
+    }
+    Ok(())
+}
+
+fn eval_source(src: &error::SourceMap) -> Result<(), ()> {
+    use parser::AstKind::Program;
+    let mut interp = interp::Interpreter::new();
+    let ast = parse_source(src)?;
+    let mut checker = typeck::TypeChecker::new();
+    let checked = checker.check(&ast);
+    // warnings are shown before the program runs
+    for w in checker.take_warnings() {
+        w.show_diagnostic(src);
+    }
+    checked.map_err(|e| {
+        e.show_diagnostic(src);
+        error::show_trace(e);
+    })?;
+    let stmts = match ast.value {
+        Program(stmts) => stmts,
+        _ => unreachable!(),
+    };
+    for stmt in stmts {
+        let n = interp.eval(&stmt).map_err(|e| {
+            e.show_diagnostic(src);
+            error::show_trace(e);
+        })?;
+        if let interp::Value::Void = n {
+            continue;
+        }
+        println!("{}", n);
+    }
+    Ok(())
+}
+
 fn main() {
-    // run_lexer();
-    // run_parser();
-    run_eval();
+    // each call in a program nests calls of the interpreter, which would
+    // soon run out of the main thread's stack
+    let command = thread::Builder::new()
+        .stack_size(interp::STACK_SIZE)
+        .spawn(run_command)
+        .expect("cannot start the interpreter thread");
+    match command.join() {
+        Ok(Ok(())) => (),
+        Ok(Err(())) => process::exit(1),
+        Err(e) => panic::resume_unwind(e),
+    }
+}
+
+fn run_command() -> Result<(), ()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        [] | ["repl"] => {
+            run_eval();
+            Ok(())
+        }
+        ["run", path] => {
+            read_source(path).and_then(|src| eval_source(&error::SourceMap::new(path, &src)))
+        }
+        ["tokens"] => {
+            run_lexer();
+            Ok(())
+        }
+        ["tokens", path] => {
+            read_source(path).and_then(|src| lex_source(&error::SourceMap::new(path, &src)))
+        }
+        ["ast"] => {
+            run_parser();
+            Ok(())
+        }
+        ["ast", path] => read_source(path)
+            .and_then(|src| parse_source(&error::SourceMap::new(path, &src)))
+            .map(|ast| println!("{:#?}", ast)),
+        ["docs", path] => {
+            read_source(path).and_then(|src| doc_source(&error::SourceMap::new(path, &src)))
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    }
 }
@@ -1,11 +1,31 @@
 use crate::error::Error;
 use crate::lexer::{lexer, Loc};
 use crate::parser::parser;
-use crate::util::Annot;
+use crate::util::{Annot, Integer};
+use std::rc::Rc;
 
 #[derive(Debug, PartialEq)]
 pub enum AstKind {
-    Num(i32),
+    Num(Integer),
+    Float(f64),
+    Str(String),
+    Bool(bool),
+    List(Vec<Ast>),
+    // `(a, b)`, with at least two elements
+    Tuple(Vec<Ast>),
+    // `{ name = a, age = b }`
+    Record(Vec<(String, Ast)>),
+    // `{ record | age = b }`, a copy of `record` with new values for some
+    // of its fields
+    Update {
+        record: Box<Ast>,
+        fields: Vec<(String, Ast)>,
+    },
+    // `record.name`
+    Field {
+        record: Box<Ast>,
+        field: String,
+    },
     UniOp {
         op: UniOp,
         e: Box<Ast>,
@@ -15,18 +35,111 @@ pub enum AstKind {
         lhs: Box<Ast>,
         rhs: Box<Ast>,
     },
+    // `doc` holds the `///` comments written just above
     Bind {
         var: String,
         body: Box<Ast>,
+        doc: Option<String>,
+    },
+    // `(q, r) := body`, binding the variables of the pattern
+    Destructure {
+        pat: Pattern,
+        body: Box<Ast>,
+    },
+    TypeDecl {
+        var: String,
+        ty: Type,
+        doc: Option<String>,
+    },
+    // `type Name = ty`, another name for `ty`
+    TypeAlias {
+        name: String,
+        ty: Type,
+        doc: Option<String>,
+    },
+    // `type Name = A ty* | B ty*`, whose values are built by `A` and `B`
+    Data {
+        name: String,
+        variants: Vec<Variant>,
+        doc: Option<String>,
     },
     Var(String),
+    Lambda {
+        param: String,
+        body: Rc<Ast>,
+    },
+    App {
+        fun: Box<Ast>,
+        arg: Box<Ast>,
+    },
+    // `list[index]`
+    Index {
+        list: Box<Ast>,
+        index: Box<Ast>,
+    },
+    Match {
+        scrutinee: Box<Ast>,
+        arms: Vec<Arm>,
+    },
+    If {
+        cond: Box<Ast>,
+        then: Box<Ast>,
+        els: Box<Ast>,
+    },
+    // the statements of an indented block, which evaluates to its last one
+    Block(Vec<Ast>),
+    Program(Vec<Ast>),
 }
 
 pub type Ast = Annot<AstKind>;
 
 impl Ast {
-    pub fn num(n: i32, loc: Loc) -> Self {
-        Self::new(AstKind::Num(n), loc)
+    pub fn num(n: impl Into<Integer>, loc: Loc) -> Self {
+        Self::new(AstKind::Num(n.into()), loc)
+    }
+
+    pub fn float(n: f64, loc: Loc) -> Self {
+        Self::new(AstKind::Float(n), loc)
+    }
+
+    pub fn str(s: impl Into<String>, loc: Loc) -> Self {
+        Self::new(AstKind::Str(s.into()), loc)
+    }
+
+    pub fn bool(b: bool, loc: Loc) -> Self {
+        Self::new(AstKind::Bool(b), loc)
+    }
+
+    pub fn list(elems: Vec<Ast>, loc: Loc) -> Self {
+        Self::new(AstKind::List(elems), loc)
+    }
+
+    pub fn tuple(elems: Vec<Ast>, loc: Loc) -> Self {
+        Self::new(AstKind::Tuple(elems), loc)
+    }
+
+    pub fn record(fields: Vec<(String, Ast)>, loc: Loc) -> Self {
+        Self::new(AstKind::Record(fields), loc)
+    }
+
+    pub fn update(record: Ast, fields: Vec<(String, Ast)>, loc: Loc) -> Self {
+        Self::new(
+            AstKind::Update {
+                record: Box::new(record),
+                fields,
+            },
+            loc,
+        )
+    }
+
+    pub fn field(record: Ast, field: impl Into<String>, loc: Loc) -> Self {
+        Self::new(
+            AstKind::Field {
+                record: Box::new(record),
+                field: field.into(),
+            },
+            loc,
+        )
     }
 
     pub fn uniop(op: UniOp, e: Ast, loc: Loc) -> Self {
@@ -45,12 +158,127 @@ impl Ast {
     }
 
     pub fn bind(var: String, body: Box<Ast>, loc: Loc) -> Self {
-        Self::new(AstKind::Bind { var, body }, loc)
+        Self::new(
+            AstKind::Bind {
+                var,
+                body,
+                doc: None,
+            },
+            loc,
+        )
+    }
+
+    pub fn destructure(pat: Pattern, body: Ast, loc: Loc) -> Self {
+        Self::new(
+            AstKind::Destructure {
+                pat,
+                body: Box::new(body),
+            },
+            loc,
+        )
+    }
+
+    pub fn type_decl(var: String, ty: Type, loc: Loc) -> Self {
+        Self::new(AstKind::TypeDecl { var, ty, doc: None }, loc)
+    }
+
+    pub fn type_alias(name: String, ty: Type, loc: Loc) -> Self {
+        Self::new(
+            AstKind::TypeAlias {
+                name,
+                ty,
+                doc: None,
+            },
+            loc,
+        )
+    }
+
+    pub fn data(name: String, variants: Vec<Variant>, loc: Loc) -> Self {
+        Self::new(
+            AstKind::Data {
+                name,
+                variants,
+                doc: None,
+            },
+            loc,
+        )
+    }
+
+    // attaches a doc comment to a binding or a type declaration or
+    // definition; other statements drop it
+    pub fn with_doc(mut self, text: Option<String>) -> Self {
+        match self.value {
+            AstKind::Bind { ref mut doc, .. }
+            | AstKind::TypeDecl { ref mut doc, .. }
+            | AstKind::TypeAlias { ref mut doc, .. }
+            | AstKind::Data { ref mut doc, .. } => *doc = text,
+            _ => (),
+        }
+        self
     }
 
     pub fn var(var: String, loc: Loc) -> Self {
         Self::new(AstKind::Var(var), loc)
     }
+
+    pub fn lambda(param: String, body: Ast, loc: Loc) -> Self {
+        Self::new(
+            AstKind::Lambda {
+                param,
+                body: Rc::new(body),
+            },
+            loc,
+        )
+    }
+
+    pub fn app(fun: Ast, arg: Ast, loc: Loc) -> Self {
+        Self::new(
+            AstKind::App {
+                fun: Box::new(fun),
+                arg: Box::new(arg),
+            },
+            loc,
+        )
+    }
+
+    pub fn index(list: Ast, index: Ast, loc: Loc) -> Self {
+        Self::new(
+            AstKind::Index {
+                list: Box::new(list),
+                index: Box::new(index),
+            },
+            loc,
+        )
+    }
+
+    pub fn match_(scrutinee: Ast, arms: Vec<Arm>, loc: Loc) -> Self {
+        Self::new(
+            AstKind::Match {
+                scrutinee: Box::new(scrutinee),
+                arms,
+            },
+            loc,
+        )
+    }
+
+    pub fn if_(cond: Ast, then: Ast, els: Ast, loc: Loc) -> Self {
+        Self::new(
+            AstKind::If {
+                cond: Box::new(cond),
+                then: Box::new(then),
+                els: Box::new(els),
+            },
+            loc,
+        )
+    }
+
+    pub fn block(stmts: Vec<Ast>, loc: Loc) -> Self {
+        Self::new(AstKind::Block(stmts), loc)
+    }
+
+    pub fn program(stmts: Vec<Ast>, loc: Loc) -> Self {
+        Self::new(AstKind::Program(stmts), loc)
+    }
 }
 
 use std::str::FromStr;
@@ -67,6 +295,7 @@ impl FromStr for Ast {
 pub enum UniOpKind {
     Plus,
     Minus,
+    Not,
 }
 
 pub type UniOp = Annot<UniOpKind>;
@@ -78,6 +307,9 @@ impl UniOp {
     pub fn minus(loc: Loc) -> Self {
         Self::new(UniOpKind::Minus, loc)
     }
+    pub fn not(loc: Loc) -> Self {
+        Self::new(UniOpKind::Not, loc)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -86,6 +318,18 @@ pub enum BinOpKind {
     Sub,
     Mul,
     Div,
+    Mod,
+    Pow,
+    Concat,
+    Cons,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
 }
 
 pub type BinOp = Annot<BinOpKind>;
@@ -103,4 +347,197 @@ impl BinOp {
     pub fn div(loc: Loc) -> Self {
         Self::new(BinOpKind::Div, loc)
     }
+    pub fn modulo(loc: Loc) -> Self {
+        Self::new(BinOpKind::Mod, loc)
+    }
+    pub fn pow(loc: Loc) -> Self {
+        Self::new(BinOpKind::Pow, loc)
+    }
+    pub fn concat(loc: Loc) -> Self {
+        Self::new(BinOpKind::Concat, loc)
+    }
+    pub fn cons(loc: Loc) -> Self {
+        Self::new(BinOpKind::Cons, loc)
+    }
+    pub fn eq(loc: Loc) -> Self {
+        Self::new(BinOpKind::Eq, loc)
+    }
+    pub fn ne(loc: Loc) -> Self {
+        Self::new(BinOpKind::Ne, loc)
+    }
+    pub fn lt(loc: Loc) -> Self {
+        Self::new(BinOpKind::Lt, loc)
+    }
+    pub fn le(loc: Loc) -> Self {
+        Self::new(BinOpKind::Le, loc)
+    }
+    pub fn gt(loc: Loc) -> Self {
+        Self::new(BinOpKind::Gt, loc)
+    }
+    pub fn ge(loc: Loc) -> Self {
+        Self::new(BinOpKind::Ge, loc)
+    }
+    pub fn and(loc: Loc) -> Self {
+        Self::new(BinOpKind::And, loc)
+    }
+    pub fn or(loc: Loc) -> Self {
+        Self::new(BinOpKind::Or, loc)
+    }
+}
+
+impl BinOpKind {
+    pub fn is_comparison(self) -> bool {
+        use self::BinOpKind::*;
+        matches!(self, Eq | Ne | Lt | Le | Gt | Ge)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TypeKind {
+    Name(String),
+    // a lowercase name in a signature, standing for any type
+    Var(String),
+    Fun(Box<Type>, Box<Type>),
+    List(Box<Type>),
+    Tuple(Vec<Type>),
+    // `{ name : A, age : B }`
+    Record(Vec<(String, Type)>),
+}
+
+pub type Type = Annot<TypeKind>;
+
+impl Type {
+    pub fn name(name: impl Into<String>, loc: Loc) -> Self {
+        Self::new(TypeKind::Name(name.into()), loc)
+    }
+    pub fn var(name: impl Into<String>, loc: Loc) -> Self {
+        Self::new(TypeKind::Var(name.into()), loc)
+    }
+    pub fn fun(arg: Type, ret: Type, loc: Loc) -> Self {
+        Self::new(TypeKind::Fun(Box::new(arg), Box::new(ret)), loc)
+    }
+    pub fn list(elem: Type, loc: Loc) -> Self {
+        Self::new(TypeKind::List(Box::new(elem)), loc)
+    }
+    pub fn tuple(elems: Vec<Type>, loc: Loc) -> Self {
+        Self::new(TypeKind::Tuple(elems), loc)
+    }
+    pub fn record(fields: Vec<(String, Type)>, loc: Loc) -> Self {
+        Self::new(TypeKind::Record(fields), loc)
+    }
+}
+
+// `A ty*` in a `type` definition
+#[derive(Debug, PartialEq)]
+pub struct Variant {
+    pub name: String,
+    pub fields: Vec<Type>,
+}
+
+impl Variant {
+    pub fn new(name: impl Into<String>, fields: Vec<Type>) -> Self {
+        Variant {
+            name: name.into(),
+            fields,
+        }
+    }
+}
+
+// `pat => body` in a `match`
+#[derive(Debug, PartialEq)]
+pub struct Arm {
+    pub pat: Pattern,
+    // `if e` after the pattern; the arm is only taken when it holds
+    pub guard: Option<Ast>,
+    pub body: Ast,
+}
+
+impl Arm {
+    pub fn new(pat: Pattern, body: Ast) -> Self {
+        Arm {
+            pat,
+            guard: None,
+            body,
+        }
+    }
+    pub fn guarded(pat: Pattern, guard: Ast, body: Ast) -> Self {
+        Arm {
+            pat,
+            guard: Some(guard),
+            body,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PatternKind {
+    Wildcard,
+    Var(String),
+    Num(Integer),
+    Str(String),
+    Bool(bool),
+    // `[p, q]`, which matches lists of exactly that length
+    List(Vec<Pattern>),
+    // `p :: ps`, which matches a non-empty list
+    Cons(Box<Pattern>, Box<Pattern>),
+    // `(p, q)`, which matches tuples of that size
+    Tuple(Vec<Pattern>),
+    // `{ name = p, age }`, which matches records whose named fields match;
+    // a field without a pattern is bound to a variable of its name
+    Record(Vec<(String, Pattern)>),
+    // `A p*`, which matches values built by the constructor `A`
+    Ctor(String, Vec<Pattern>),
+}
+
+pub type Pattern = Annot<PatternKind>;
+
+impl Pattern {
+    pub fn wildcard(loc: Loc) -> Self {
+        Self::new(PatternKind::Wildcard, loc)
+    }
+    pub fn var(var: impl Into<String>, loc: Loc) -> Self {
+        Self::new(PatternKind::Var(var.into()), loc)
+    }
+    pub fn num(n: impl Into<Integer>, loc: Loc) -> Self {
+        Self::new(PatternKind::Num(n.into()), loc)
+    }
+    pub fn str(s: impl Into<String>, loc: Loc) -> Self {
+        Self::new(PatternKind::Str(s.into()), loc)
+    }
+    pub fn bool(b: bool, loc: Loc) -> Self {
+        Self::new(PatternKind::Bool(b), loc)
+    }
+    pub fn list(elems: Vec<Pattern>, loc: Loc) -> Self {
+        Self::new(PatternKind::List(elems), loc)
+    }
+    pub fn cons(head: Pattern, tail: Pattern, loc: Loc) -> Self {
+        Self::new(PatternKind::Cons(Box::new(head), Box::new(tail)), loc)
+    }
+    pub fn tuple(elems: Vec<Pattern>, loc: Loc) -> Self {
+        Self::new(PatternKind::Tuple(elems), loc)
+    }
+    pub fn record(fields: Vec<(String, Pattern)>, loc: Loc) -> Self {
+        Self::new(PatternKind::Record(fields), loc)
+    }
+    pub fn ctor(name: impl Into<String>, args: Vec<Pattern>, loc: Loc) -> Self {
+        Self::new(PatternKind::Ctor(name.into(), args), loc)
+    }
+
+    /// The variables the pattern binds, from left to right.
+    pub fn vars(&self) -> Vec<&str> {
+        use self::PatternKind::*;
+        match self.value {
+            Var(ref var) => vec![var.as_str()],
+            List(ref pats) | Tuple(ref pats) | Ctor(_, ref pats) => {
+                pats.iter().flat_map(Pattern::vars).collect()
+            }
+            Record(ref fields) => fields.iter().flat_map(|(_, pat)| pat.vars()).collect(),
+            Cons(ref head, ref tail) => {
+                let mut vars = head.vars();
+                vars.extend(tail.vars());
+                vars
+            }
+            Wildcard | Num(_) | Str(_) | Bool(_) => Vec::new(),
+        }
+    }
 }
@@ -5,8 +5,9 @@ pub enum ParserError {
     RedundantExpression(Token),
     UnexpectedToken(Token),
     UnclosedOpenParen(Token),
-    NotOperator(Token),
     NotExpression(Token),
+    NotType(Token),
+    NotPattern(Token),
     Eof,
 }
 
@@ -15,19 +16,12 @@ impl fmt::Display for ParserError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use crate::parser::ParserError::*;
         match self {
-            UnexpectedToken(tok) => write!(f, "{}: {} is not expected", tok.loc, tok.value),
-            NotExpression(tok) => write!(
-                f,
-                "{}: '{}' is not a start of expression",
-                tok.loc, tok.value
-            ),
-            NotOperator(tok) => write!(f, "{}: '{}' is not an operator", tok.loc, tok.value),
-            UnclosedOpenParen(tok) => write!(f, "{}: '{}' is not closed", tok.loc, tok.value),
-            RedundantExpression(tok) => write!(
-                f,
-                "{}: expression after '{}' is redundant",
-                tok.loc, tok.value
-            ),
+            UnexpectedToken(tok) => write!(f, "{} is not expected", tok.value),
+            NotExpression(tok) => write!(f, "'{}' is not a start of expression", tok.value),
+            NotType(tok) => write!(f, "'{}' is not a start of type", tok.value),
+            NotPattern(tok) => write!(f, "'{}' is not a start of pattern", tok.value),
+            UnclosedOpenParen(tok) => write!(f, "'{}' is not closed", tok.value),
+            RedundantExpression(tok) => write!(f, "expression after '{}' is redundant", tok.value),
             Eof => write!(f, "End of file"),
         }
     }
@@ -1,5 +1,6 @@
 mod ast;
 mod error;
+#[allow(clippy::module_inception)]
 mod parser;
 
 pub use ast::*;
@@ -1,53 +1,664 @@
-use crate::lexer::{Token, TokenKind};
-use crate::parser::ast::{Ast, BinOp, UniOp};
+use crate::lexer::{Loc, Token, TokenKind};
+use crate::parser::ast::{Arm, Ast, BinOp, Pattern, PatternKind, Type, UniOp, Variant};
 use crate::parser::error::ParserError;
 use itertools::{multipeek, MultiPeek};
-use std::iter::IntoIterator;
 
+// program ::= newline* (stmt (newline+ stmt)*)? newline*
 pub fn parser(tokens: Vec<Token>) -> Result<Ast, ParserError> {
-    let mut tokens = multipeek(tokens.into_iter());
-    let ast = parse_stmt(&mut tokens)?;
-    match tokens.next() {
-        Some(tok) => Err(ParserError::RedundantExpression(tok)),
-        None => Ok(ast),
+    let mut tokens = multipeek(tokens);
+    let mut stmts = Vec::new();
+    loop {
+        skip_newlines(&mut tokens);
+        if tokens.peek().is_none() {
+            break;
+        }
+        stmts.extend(parse_stmt(&mut tokens)?);
+        match tokens.next() {
+            Some(Token {
+                value: TokenKind::Newline,
+                ..
+            })
+            | None => (),
+            Some(tok) => return Err(ParserError::RedundantExpression(tok)),
+        }
+    }
+    let loc = match (stmts.first(), stmts.last()) {
+        (Some(first), Some(last)) => first.loc.merge(&last.loc),
+        _ => Loc(0, 0),
+    };
+    Ok(Ast::program(stmts, loc))
+}
+
+fn skip_newlines<Tokens: Iterator<Item = Token>>(tokens: &mut MultiPeek<Tokens>) {
+    tokens.reset_peek();
+    while let Some(TokenKind::Newline) = tokens.peek().map(|tok| tok.value.clone()) {
+        tokens.next();
+        tokens.reset_peek();
     }
+    tokens.reset_peek();
 }
 
-// stmt ::= expr
+// stmt ::= (doc_comment newline+)* (var ":=" body | var "::=" type | fn | type_def | expr)
+// a `fn` declaration gives two statements, so this returns a list
 fn parse_stmt<Tokens: Iterator<Item = Token>>(
     tokens: &mut MultiPeek<Tokens>,
-) -> Result<Ast, ParserError> {
+) -> Result<Vec<Ast>, ParserError> {
+    let mut doc: Option<String> = None;
+    loop {
+        tokens.reset_peek();
+        match tokens.peek().map(|tok| tok.value.clone()) {
+            Some(TokenKind::DocComment(text)) => {
+                tokens.next();
+                doc = Some(match doc {
+                    Some(doc) => doc + "\n" + &text,
+                    None => text,
+                });
+                skip_newlines(tokens);
+            }
+            // a doc at the end of a block or file documents nothing
+            Some(TokenKind::Dedent) | None if doc.is_some() => return Ok(Vec::new()),
+            _ => break,
+        }
+    }
+    // the doc belongs to the first statement, the signature of a `fn`
+    Ok(parse_bare_stmt(tokens)?
+        .into_iter()
+        .map(|stmt| stmt.with_doc(doc.take()))
+        .collect())
+}
+
+fn parse_bare_stmt<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<Vec<Ast>, ParserError> {
+    tokens.reset_peek();
     match tokens.peek().map(|tok| tok.value.clone()) {
+        Some(TokenKind::Fn) => parse_fn(tokens),
+        Some(TokenKind::Type) => Ok(vec![parse_type_def(tokens)?]),
         Some(TokenKind::Var(_)) => match tokens.peek().map(|tok| tok.value.clone()) {
             Some(TokenKind::Bind) => {
-                let var = match tokens.next() {
-                    Some(Token {
-                        value: TokenKind::Var(s),
-                        loc,
-                    }) => (s, loc),
-                    _ => unreachable!(),
-                };
-                match tokens.next() {
-                    Some(Token {
-                        value: TokenKind::Bind,
-                        loc: _,
-                    }) => (),
-                    _ => unreachable!(),
-                };
-                let body = parse_expr(tokens)?;
+                let var = parse_var(tokens);
+                tokens.next();
+                let body = parse_body(tokens)?;
                 let loc = var.1.merge(&body.loc);
-                Ok(Ast::bind(var.0, Box::new(body), loc))
+                Ok(vec![Ast::bind(var.0, Box::new(body), loc)])
+            }
+            Some(TokenKind::TypeBind) => {
+                let var = parse_var(tokens);
+                tokens.next();
+                let ty = parse_type(tokens)?;
+                let loc = var.1.merge(&ty.loc);
+                Ok(vec![Ast::type_decl(var.0, ty, loc)])
             }
-            _ => parse_expr(tokens),
+            _ => Ok(vec![parse_expr(tokens)?]),
         },
+        Some(TokenKind::Lparen)
+            if peek_past_parens(tokens)
+                && tokens.peek().map(|tok| tok.value.clone()) == Some(TokenKind::Bind) =>
+        {
+            let pat = parse_simple_pattern(tokens)?;
+            tokens.next();
+            let body = parse_body(tokens)?;
+            let loc = pat.loc.merge(&body.loc);
+            Ok(vec![Ast::destructure(pat, body, loc)])
+        }
+        _ => Ok(vec![parse_expr(tokens)?]),
+    }
+}
+
+// fn ::= "fn" var param+ ("=>" body | ":=" type ("=>" body | block))
+// `fn f x := T => e` is `f ::= T` followed by `f := x => e`
+fn parse_fn<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<Vec<Ast>, ParserError> {
+    let start = tokens.next().unwrap().loc;
+    tokens.reset_peek();
+    let var = match tokens.peek().map(|tok| tok.value.clone()) {
+        Some(TokenKind::Var(_)) => Some(parse_var(tokens).0),
+        _ => None,
+    };
+    let params = parse_params(tokens)?;
+    let var = match var {
+        Some(var) if !params.is_empty() => var,
+        _ => {
+            return Err(tokens
+                .next()
+                .map(ParserError::UnexpectedToken)
+                .unwrap_or(ParserError::Eof))
+        }
+    };
+    let mut stmts = Vec::new();
+    tokens.reset_peek();
+    if let Some(TokenKind::Bind) = tokens.peek().map(|tok| tok.value.clone()) {
+        tokens.next();
+        let ty = parse_type(tokens)?;
+        let loc = start.merge(&ty.loc);
+        stmts.push(Ast::type_decl(var.clone(), ty, loc));
+        tokens.reset_peek();
+        if let Some(TokenKind::Indent) = tokens.peek().map(|tok| tok.value.clone()) {
+            let body = parse_block(tokens)?;
+            stmts.push(fn_bind(start, var, params, body));
+            return Ok(stmts);
+        }
+    }
+    expect_token(tokens, TokenKind::FatArrow)?;
+    let body = parse_body(tokens)?;
+    stmts.push(fn_bind(start, var, params, body));
+    Ok(stmts)
+}
+
+fn fn_bind(start: Loc, var: String, params: Vec<Pattern>, body: Ast) -> Ast {
+    let loc = start.merge(&body.loc);
+    Ast::bind(var, Box::new(curry(params, body)), loc)
+}
+
+// body ::= block | expr
+fn parse_body<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<Ast, ParserError> {
+    tokens.reset_peek();
+    match tokens.peek().map(|tok| tok.value.clone()) {
+        Some(TokenKind::Indent) => parse_block(tokens),
         _ => parse_expr(tokens),
     }
 }
 
-// expr ::= term expr_loop
-// expr_loop ::= ("+" | "-") expr_loop | ε
+// block ::= indent stmt (newline+ stmt)* dedent
+fn parse_block<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<Ast, ParserError> {
+    expect_token(tokens, TokenKind::Indent)?;
+    let mut stmts = parse_stmt(tokens)?;
+    loop {
+        match tokens.next() {
+            Some(Token {
+                value: TokenKind::Newline,
+                ..
+            }) => {
+                skip_newlines(tokens);
+                stmts.extend(parse_stmt(tokens)?);
+            }
+            // a block of only doc comments has no value
+            Some(
+                tok @ Token {
+                    value: TokenKind::Dedent,
+                    ..
+                },
+            ) if stmts.is_empty() => return Err(ParserError::NotExpression(tok)),
+            Some(Token {
+                value: TokenKind::Dedent,
+                ..
+            }) => break,
+            Some(t) => return Err(ParserError::RedundantExpression(t)),
+            None => return Err(ParserError::Eof),
+        }
+    }
+    let loc = stmts[0].loc.merge(&stmts[stmts.len() - 1].loc);
+    Ok(Ast::block(stmts, loc))
+}
+
+// consumes a `Var` token the caller has already peeked
+fn parse_var<Tokens: Iterator<Item = Token>>(tokens: &mut MultiPeek<Tokens>) -> (String, Loc) {
+    match tokens.next() {
+        Some(Token {
+            value: TokenKind::Var(s),
+            loc,
+        }) => (s, loc),
+        _ => unreachable!(),
+    }
+}
+
+// consumes the next token, which must be of the given kind
+fn expect_token<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+    kind: TokenKind,
+) -> Result<Loc, ParserError> {
+    match tokens.next() {
+        Some(Token { value, loc }) if value == kind => Ok(loc),
+        Some(t) => Err(ParserError::UnexpectedToken(t)),
+        None => Err(ParserError::Eof),
+    }
+}
+
+// expr ::= param+ "=>" body | match | if | or
+// `x n => e` is the curried `x => n => e`
 fn parse_expr<Tokens: Iterator<Item = Token>>(
     tokens: &mut MultiPeek<Tokens>,
+) -> Result<Ast, ParserError> {
+    tokens.reset_peek();
+    match tokens.peek().map(|tok| tok.value.clone()) {
+        Some(TokenKind::Match) => return parse_match(tokens),
+        Some(TokenKind::If) => return parse_if(tokens),
+        _ => (),
+    }
+    tokens.reset_peek();
+    let mut arity = 0;
+    let is_lambda = loop {
+        match tokens.peek().map(|tok| tok.value.clone()) {
+            Some(TokenKind::Var(_)) => arity += 1,
+            Some(TokenKind::Lparen) if peek_past_parens(tokens) => arity += 1,
+            Some(TokenKind::FatArrow) => break arity > 0,
+            _ => break false,
+        }
+    };
+    tokens.reset_peek();
+    if !is_lambda {
+        return parse_or(tokens);
+    }
+    let params = parse_params(tokens)?;
+    tokens.next();
+    let body = parse_body(tokens)?;
+    Ok(curry(params, body))
+}
+
+// `curry([x, n], e)` is `x => n => e`. A pattern parameter such as
+// `(a, b)` is matched against the argument: `(a, b) => e` is
+// `arg => match arg | (a, b) => e`.
+fn curry(params: Vec<Pattern>, body: Ast) -> Ast {
+    params.into_iter().rev().fold(body, |body, param| {
+        let loc = param.loc.merge(&body.loc);
+        if let PatternKind::Var(ref var) = param.value {
+            return Ast::lambda(var.clone(), body, loc);
+        }
+        let arg = Ast::var(PATTERN_ARG.to_string(), param.loc.clone());
+        let body = Ast::match_(arg, vec![Arm::new(param, body)], loc.clone());
+        Ast::lambda(PATTERN_ARG.to_string(), body, loc)
+    })
+}
+
+// the parameter of a lambda whose parameter is a pattern; it cannot be
+// written, so it hides no variable of the program
+const PATTERN_ARG: &str = "(arg)";
+
+// param ::= var | "(" pattern ("," pattern)* ")"
+fn parse_params<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<Vec<Pattern>, ParserError> {
+    let mut params = Vec::new();
+    loop {
+        tokens.reset_peek();
+        match tokens.peek().map(|tok| tok.value.clone()) {
+            Some(TokenKind::Var(_)) => {
+                let (var, loc) = parse_var(tokens);
+                params.push(Pattern::var(var, loc));
+            }
+            Some(TokenKind::Lparen) => params.push(parse_simple_pattern(tokens)?),
+            _ => return Ok(params),
+        }
+    }
+}
+
+// peeks past the tokens up to the `)` closing the `(` just peeked,
+// returning whether there is one
+fn peek_past_parens<Tokens: Iterator<Item = Token>>(tokens: &mut MultiPeek<Tokens>) -> bool {
+    let mut depth = 1;
+    while depth > 0 {
+        match tokens.peek().map(|tok| tok.value.clone()) {
+            Some(TokenKind::Lparen) => depth += 1,
+            Some(TokenKind::Rparen) => depth -= 1,
+            Some(_) => (),
+            None => return false,
+        }
+    }
+    true
+}
+
+// match ::= "match" or (("|" arm)+ | indent "|"? arm (newline+ "|"? arm)* dedent)
+// arms following a nested `match` on the same line belong to it unless it is
+// parenthesized
+fn parse_match<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<Ast, ParserError> {
+    let start = tokens.next().unwrap().loc;
+    let scrutinee = parse_or(tokens)?;
+    let mut arms = Vec::new();
+    tokens.reset_peek();
+    match tokens.peek().map(|tok| tok.value.clone()) {
+        Some(TokenKind::Indent) => {
+            tokens.next();
+            arms.push(parse_arm(tokens)?);
+            loop {
+                match tokens.next() {
+                    Some(Token {
+                        value: TokenKind::Newline,
+                        ..
+                    }) => {
+                        skip_newlines(tokens);
+                        arms.push(parse_arm(tokens)?);
+                    }
+                    Some(Token {
+                        value: TokenKind::Dedent,
+                        ..
+                    }) => break,
+                    Some(t) => return Err(ParserError::RedundantExpression(t)),
+                    None => return Err(ParserError::Eof),
+                }
+            }
+        }
+        Some(TokenKind::Bar) => {
+            tokens.reset_peek();
+            while let Some(TokenKind::Bar) = tokens.peek().map(|tok| tok.value.clone()) {
+                arms.push(parse_arm(tokens)?);
+                tokens.reset_peek();
+            }
+        }
+        _ => {
+            tokens.reset_peek();
+            return Err(tokens
+                .next()
+                .map(ParserError::UnexpectedToken)
+                .unwrap_or(ParserError::Eof));
+        }
+    }
+    let loc = start.merge(&arms[arms.len() - 1].body.loc);
+    Ok(Ast::match_(scrutinee, arms, loc))
+}
+
+// arm ::= "|"? (pattern ("if" or)? | var var) "=>" body
+// `p x => e` is shorthand for `x if p x => e`
+fn parse_arm<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<Arm, ParserError> {
+    tokens.reset_peek();
+    if let Some(TokenKind::Bar) = tokens.peek().map(|tok| tok.value.clone()) {
+        tokens.next();
+    }
+    tokens.reset_peek();
+    let mut peek = || tokens.peek().map(|tok| tok.value.clone());
+    let shorthand = matches!(
+        (peek(), peek()),
+        (Some(TokenKind::Var(_)), Some(TokenKind::Var(_)))
+    );
+    let (pat, guard) = if shorthand {
+        let (pred, pred_loc) = parse_var(tokens);
+        let (var, loc) = parse_var(tokens);
+        let arg = Ast::var(var.clone(), loc.clone());
+        let guard = Ast::app(Ast::var(pred, pred_loc.clone()), arg, pred_loc.merge(&loc));
+        (Pattern::var(var, loc), Some(guard))
+    } else {
+        let pat = parse_pattern(tokens)?;
+        tokens.reset_peek();
+        let guard = match tokens.peek().map(|tok| tok.value.clone()) {
+            Some(TokenKind::If) => {
+                tokens.next();
+                Some(parse_or(tokens)?)
+            }
+            _ => None,
+        };
+        (pat, guard)
+    };
+    expect_token(tokens, TokenKind::FatArrow)?;
+    let body = parse_body(tokens)?;
+    Ok(match guard {
+        Some(guard) => Arm::guarded(pat, guard, body),
+        None => Arm::new(pat, body),
+    })
+}
+
+// pattern ::= ctor_pattern ("::" pattern)?
+fn parse_pattern<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<Pattern, ParserError> {
+    let head = parse_ctor_pattern(tokens)?;
+    tokens.reset_peek();
+    match tokens.peek().map(|tok| tok.value.clone()) {
+        Some(TokenKind::ColonColon) => {
+            tokens.next();
+            let tail = parse_pattern(tokens)?;
+            let loc = head.loc.merge(&tail.loc);
+            Ok(Pattern::cons(head, tail, loc))
+        }
+        _ => Ok(head),
+    }
+}
+
+// ctor_pattern ::= upper simple_pattern* | simple_pattern
+fn parse_ctor_pattern<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<Pattern, ParserError> {
+    tokens.reset_peek();
+    let (name, mut loc) = match tokens.peek().map(|tok| tok.value.clone()) {
+        Some(TokenKind::Upper(_)) => match tokens.next() {
+            Some(Token {
+                value: TokenKind::Upper(name),
+                loc,
+            }) => (name, loc),
+            _ => unreachable!(),
+        },
+        _ => return parse_simple_pattern(tokens),
+    };
+    let mut args = Vec::new();
+    loop {
+        tokens.reset_peek();
+        match tokens.peek().map(|tok| tok.value.clone()) {
+            Some(TokenKind::Underscore)
+            | Some(TokenKind::Var(_))
+            | Some(TokenKind::Upper(_))
+            | Some(TokenKind::Number(_))
+            | Some(TokenKind::Minus)
+            | Some(TokenKind::Str(_))
+            | Some(TokenKind::True)
+            | Some(TokenKind::False)
+            | Some(TokenKind::Lbracket)
+            | Some(TokenKind::Lparen)
+            | Some(TokenKind::Lbrace) => {
+                let arg = parse_simple_pattern(tokens)?;
+                loc = loc.merge(&arg.loc);
+                args.push(arg);
+            }
+            _ => return Ok(Pattern::ctor(name, args, loc)),
+        }
+    }
+}
+
+// simple_pattern ::= upper | "_" | var | "-"? nat | string | "True" | "False"
+//                  | "[" (pattern ("," pattern)* ","?)? "]" | "(" pattern ")"
+//                  | "{" (var ("=" pattern)? ("," var ("=" pattern)?)* ","?)? "}"
+fn parse_simple_pattern<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<Pattern, ParserError> {
+    tokens
+        .next()
+        .ok_or(ParserError::Eof)
+        .and_then(|tok| match tok.value {
+            TokenKind::Underscore => Ok(Pattern::wildcard(tok.loc)),
+            // a constructor without arguments
+            TokenKind::Upper(s) => Ok(Pattern::ctor(s, vec![], tok.loc)),
+            TokenKind::Var(s) => Ok(Pattern::var(s, tok.loc)),
+            TokenKind::Number(n) => Ok(Pattern::num(n, tok.loc)),
+            TokenKind::Str(s) => Ok(Pattern::str(s, tok.loc)),
+            TokenKind::True => Ok(Pattern::bool(true, tok.loc)),
+            TokenKind::False => Ok(Pattern::bool(false, tok.loc)),
+            TokenKind::Minus => match tokens.next() {
+                Some(Token {
+                    value: TokenKind::Number(n),
+                    loc,
+                }) => Ok(Pattern::num(n.neg(), tok.loc.merge(&loc))),
+                Some(t) => Err(ParserError::NotPattern(t)),
+                None => Err(ParserError::Eof),
+            },
+            TokenKind::Lbracket => {
+                let start = tok.loc.clone();
+                let (elems, close) = parse_seq(tokens, tok, TokenKind::Rbracket, parse_pattern)?;
+                Ok(Pattern::list(elems, start.merge(&close)))
+            }
+            TokenKind::Lbrace => {
+                let start = tok.loc.clone();
+                let (fields, close) =
+                    parse_seq(tokens, tok, TokenKind::Rbrace, parse_field_pattern)?;
+                Ok(Pattern::record(fields, start.merge(&close)))
+            }
+            TokenKind::Lparen => {
+                let pat = parse_pattern(tokens)?;
+                match tokens.next() {
+                    Some(Token {
+                        value: TokenKind::Rparen,
+                        ..
+                    }) => Ok(pat),
+                    Some(Token {
+                        value: TokenKind::Comma,
+                        ..
+                    }) => {
+                        let start = tok.loc.clone();
+                        let (elems, close) = parse_tuple(tokens, tok, pat, parse_pattern)?;
+                        Ok(Pattern::tuple(elems, start.merge(&close)))
+                    }
+                    Some(t) => Err(ParserError::UnexpectedToken(t)),
+                    None => Err(ParserError::UnclosedOpenParen(tok)),
+                }
+            }
+            _ => Err(ParserError::NotPattern(tok)),
+        })
+}
+
+// a field of a record pattern, `name = pattern` or `name`, which binds the
+// field to `name`
+fn parse_field_pattern<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<(String, Pattern), ParserError> {
+    let (name, loc) = match tokens.next() {
+        Some(Token {
+            value: TokenKind::Var(name),
+            loc,
+        }) => (name, loc),
+        Some(t) => return Err(ParserError::UnexpectedToken(t)),
+        None => return Err(ParserError::Eof),
+    };
+    tokens.reset_peek();
+    if let Some(TokenKind::Equal) = tokens.peek().map(|tok| tok.value.clone()) {
+        tokens.next();
+        return Ok((name, parse_pattern(tokens)?));
+    }
+    let pat = Pattern::var(name.clone(), loc);
+    Ok((name, pat))
+}
+
+// if ::= "if" expr "then" expr "else" expr
+fn parse_if<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<Ast, ParserError> {
+    let start = tokens.next().unwrap().loc;
+    let cond = parse_expr(tokens)?;
+    expect_token(tokens, TokenKind::Then)?;
+    let then = parse_expr(tokens)?;
+    expect_token(tokens, TokenKind::Else)?;
+    let els = parse_expr(tokens)?;
+    let loc = start.merge(&els.loc);
+    Ok(Ast::if_(cond, then, els, loc))
+}
+
+// or ::= and ("||" and)*
+fn parse_or<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<Ast, ParserError> {
+    let mut lhs = parse_and(tokens)?;
+    loop {
+        tokens.reset_peek();
+        match tokens.peek().map(|tok| tok.value.clone()) {
+            Some(TokenKind::OrOr) => {
+                let op = BinOp::or(tokens.next().unwrap().loc);
+                let rhs = parse_and(tokens)?;
+                let loc = lhs.loc.merge(&rhs.loc);
+                lhs = Ast::binop(op, lhs, rhs, loc)
+            }
+            _ => return Ok(lhs),
+        }
+    }
+}
+
+// and ::= comparison ("&&" comparison)*
+fn parse_and<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<Ast, ParserError> {
+    let mut lhs = parse_comparison(tokens)?;
+    loop {
+        tokens.reset_peek();
+        match tokens.peek().map(|tok| tok.value.clone()) {
+            Some(TokenKind::AndAnd) => {
+                let op = BinOp::and(tokens.next().unwrap().loc);
+                let rhs = parse_comparison(tokens)?;
+                let loc = lhs.loc.merge(&rhs.loc);
+                lhs = Ast::binop(op, lhs, rhs, loc)
+            }
+            _ => return Ok(lhs),
+        }
+    }
+}
+
+// comparison ::= cons (("==" | "!=" | "<" | "<=" | ">" | ">=") cons)?
+// comparisons do not chain, so `a < b < c` is an error
+fn parse_comparison<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<Ast, ParserError> {
+    fn comparison_op(tok: &Token) -> Option<BinOp> {
+        let loc = tok.loc.clone();
+        match tok.value {
+            TokenKind::EqEq => Some(BinOp::eq(loc)),
+            TokenKind::NotEq => Some(BinOp::ne(loc)),
+            TokenKind::Lt => Some(BinOp::lt(loc)),
+            TokenKind::LtEq => Some(BinOp::le(loc)),
+            TokenKind::Gt => Some(BinOp::gt(loc)),
+            TokenKind::GtEq => Some(BinOp::ge(loc)),
+            _ => None,
+        }
+    }
+
+    let lhs = parse_cons(tokens)?;
+    tokens.reset_peek();
+    let op = match tokens.peek().and_then(comparison_op) {
+        Some(op) => op,
+        None => return Ok(lhs),
+    };
+    tokens.next();
+    let rhs = parse_cons(tokens)?;
+    tokens.reset_peek();
+    if tokens.peek().and_then(comparison_op).is_some() {
+        return Err(ParserError::UnexpectedToken(tokens.next().unwrap()));
+    }
+    let loc = lhs.loc.merge(&rhs.loc);
+    Ok(Ast::binop(op, lhs, rhs, loc))
+}
+
+// cons ::= concat ("::" cons)?
+// `x :: xs ++ ys` is `x :: (xs ++ ys)`
+fn parse_cons<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<Ast, ParserError> {
+    let head = parse_concat(tokens)?;
+    tokens.reset_peek();
+    match tokens.peek().map(|tok| tok.value.clone()) {
+        Some(TokenKind::ColonColon) => {
+            let op = BinOp::cons(tokens.next().unwrap().loc);
+            let tail = parse_cons(tokens)?;
+            let loc = head.loc.merge(&tail.loc);
+            Ok(Ast::binop(op, head, tail, loc))
+        }
+        _ => Ok(head),
+    }
+}
+
+// concat ::= arith ("++" arith)*
+fn parse_concat<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<Ast, ParserError> {
+    let mut lhs = parse_arith(tokens)?;
+    loop {
+        tokens.reset_peek();
+        match tokens.peek().map(|tok| tok.value.clone()) {
+            Some(TokenKind::PlusPlus) => {
+                let op = BinOp::concat(tokens.next().unwrap().loc);
+                let rhs = parse_arith(tokens)?;
+                let loc = lhs.loc.merge(&rhs.loc);
+                lhs = Ast::binop(op, lhs, rhs, loc)
+            }
+            _ => return Ok(lhs),
+        }
+    }
+}
+
+// arith ::= term arith_loop
+// arith_loop ::= ("+" | "-") arith_loop | ε
+fn parse_arith<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
 ) -> Result<Ast, ParserError> {
     tokens.reset_peek();
     let mut lhs = parse_term(tokens)?;
@@ -77,7 +688,7 @@ fn parse_expr<Tokens: Iterator<Item = Token>>(
 }
 
 // term ::= unnary term_loop
-// term_loop ::= ("*" | "/") unnary term_loop | ε
+// term_loop ::= ("*" | "/" | "%") unnary term_loop | ε
 fn parse_term<Tokens: Iterator<Item = Token>>(
     tokens: &mut MultiPeek<Tokens>,
 ) -> Result<Ast, ParserError> {
@@ -86,7 +697,7 @@ fn parse_term<Tokens: Iterator<Item = Token>>(
     tokens.reset_peek();
     loop {
         match tokens.peek().map(|tok| tok.value.clone()) {
-            Some(TokenKind::Asterisk) | Some(TokenKind::Slash) => {
+            Some(TokenKind::Asterisk) | Some(TokenKind::Slash) | Some(TokenKind::Percent) => {
                 let op = match tokens.next() {
                     Some(Token {
                         value: TokenKind::Asterisk,
@@ -96,6 +707,10 @@ fn parse_term<Tokens: Iterator<Item = Token>>(
                         value: TokenKind::Slash,
                         loc,
                     }) => BinOp::div(loc),
+                    Some(Token {
+                        value: TokenKind::Percent,
+                        loc,
+                    }) => BinOp::modulo(loc),
                     _ => unreachable!(),
                 };
                 let rhs = parse_unary(tokens)?;
@@ -108,12 +723,12 @@ fn parse_term<Tokens: Iterator<Item = Token>>(
     }
 }
 
-// unary ::= factor | ("+" | "-") factor
+// unary ::= power | ("+" | "-" | "!") power
 fn parse_unary<Tokens: Iterator<Item = Token>>(
     tokens: &mut MultiPeek<Tokens>,
 ) -> Result<Ast, ParserError> {
     match tokens.peek().map(|tok| tok.value.clone()) {
-        Some(TokenKind::Plus) | Some(TokenKind::Minus) => {
+        Some(TokenKind::Plus) | Some(TokenKind::Minus) | Some(TokenKind::Bang) => {
             let op = match tokens.next() {
                 Some(Token {
                     value: TokenKind::Plus,
@@ -123,45 +738,478 @@ fn parse_unary<Tokens: Iterator<Item = Token>>(
                     value: TokenKind::Minus,
                     loc,
                 }) => UniOp::minus(loc),
+                Some(Token {
+                    value: TokenKind::Bang,
+                    loc,
+                }) => UniOp::not(loc),
                 _ => unreachable!(),
             };
-            let e = parse_factor(tokens)?;
-            let loc = e.loc.merge(&e.loc);
+            let e = parse_power(tokens)?;
+            let loc = op.loc.merge(&e.loc);
             Ok(Ast::uniop(op, e, loc))
         }
-        _ => parse_factor(tokens),
+        _ => parse_power(tokens),
+    }
+}
+
+// power ::= app ("^" unary)?
+// `^` is right associative and binds tighter than a sign: `-2 ^ 2` is `-(2 ^ 2)`
+fn parse_power<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<Ast, ParserError> {
+    let lhs = parse_app(tokens)?;
+    tokens.reset_peek();
+    match tokens.peek().map(|tok| tok.value.clone()) {
+        Some(TokenKind::Caret) => {
+            let op = match tokens.next() {
+                Some(Token {
+                    value: TokenKind::Caret,
+                    loc,
+                }) => BinOp::pow(loc),
+                _ => unreachable!(),
+            };
+            let rhs = parse_unary(tokens)?;
+            let loc = lhs.loc.merge(&rhs.loc);
+            Ok(Ast::binop(op, lhs, rhs, loc))
+        }
+        _ => Ok(lhs),
+    }
+}
+
+// app ::= factor factor*
+fn parse_app<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<Ast, ParserError> {
+    let mut fun = parse_factor(tokens)?;
+    loop {
+        tokens.reset_peek();
+        match tokens.peek().map(|tok| tok.value.clone()) {
+            Some(TokenKind::Number(_))
+            | Some(TokenKind::Float(_))
+            | Some(TokenKind::Str(_))
+            | Some(TokenKind::True)
+            | Some(TokenKind::False)
+            | Some(TokenKind::Var(_))
+            | Some(TokenKind::Upper(_))
+            | Some(TokenKind::Lparen)
+            | Some(TokenKind::Lbracket)
+            | Some(TokenKind::Lbrace) => {
+                let arg = parse_factor(tokens)?;
+                let loc = fun.loc.merge(&arg.loc);
+                fun = Ast::app(fun, arg, loc)
+            }
+            _ => return Ok(fun),
+        }
     }
 }
 
-// factor ::= nat | "(" expr ")"
+// factor ::= atom ("[" expr "]" | "." var)*
+// `xs[i]` indexes only when the "[" touches what comes before it; `f [1]`
+// applies `f` to a list. So does `r.name` take a field.
 fn parse_factor<Tokens: Iterator<Item = Token>>(
     tokens: &mut MultiPeek<Tokens>,
 ) -> Result<Ast, ParserError> {
-    tokens
+    let (mut e, mut end) = parse_atom(tokens)?;
+    loop {
+        tokens.reset_peek();
+        match tokens.peek().map(|tok| (tok.value.clone(), tok.loc.0)) {
+            Some((TokenKind::Lbracket, start)) if start == end => {
+                let open = tokens.next().unwrap();
+                let index = parse_expr(tokens)?;
+                let close = match tokens.next() {
+                    Some(Token {
+                        value: TokenKind::Rbracket,
+                        loc,
+                    }) => loc,
+                    Some(t) => return Err(ParserError::UnexpectedToken(t)),
+                    None => return Err(ParserError::UnclosedOpenParen(open)),
+                };
+                end = close.1;
+                let loc = e.loc.merge(&close);
+                e = Ast::index(e, index, loc);
+            }
+            Some((TokenKind::Dot, start)) if start == end => {
+                tokens.next();
+                let (field, loc) = match tokens.next() {
+                    Some(Token {
+                        value: TokenKind::Var(field),
+                        loc,
+                    }) => (field, loc),
+                    Some(t) => return Err(ParserError::UnexpectedToken(t)),
+                    None => return Err(ParserError::Eof),
+                };
+                end = loc.1;
+                let loc = e.loc.merge(&loc);
+                e = Ast::field(e, field, loc);
+            }
+            _ => return Ok(e),
+        }
+    }
+}
+
+// atom ::= nat | float | string | "True" | "False" | var | upper | "(" expr ")"
+//        | "[" (expr ("," expr)* ","?)? "]" | record
+// returns the atom along with where its last token ends
+fn parse_atom<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<(Ast, usize), ParserError> {
+    tokens
         .next()
         .ok_or(ParserError::Eof)
         .and_then(|tok| match tok.value {
-            TokenKind::Number(n) => Ok(Ast::num(n, tok.loc)),
+            TokenKind::Number(n) => Ok((Ast::num(n, tok.loc.clone()), tok.loc.1)),
+            TokenKind::Float(n) => Ok((Ast::float(n, tok.loc.clone()), tok.loc.1)),
+            TokenKind::Str(s) => Ok((Ast::str(s, tok.loc.clone()), tok.loc.1)),
+            TokenKind::True => Ok((Ast::bool(true, tok.loc.clone()), tok.loc.1)),
+            TokenKind::False => Ok((Ast::bool(false, tok.loc.clone()), tok.loc.1)),
             TokenKind::Lparen => {
                 let e = parse_expr(tokens)?;
                 match tokens.next() {
                     Some(Token {
                         value: TokenKind::Rparen,
+                        loc,
+                    }) => Ok((e, loc.1)),
+                    Some(Token {
+                        value: TokenKind::Comma,
                         ..
-                    }) => Ok(e),
-                    Some(t) => Err(ParserError::RedundantExpression(t)),
+                    }) => {
+                        let start = tok.loc.clone();
+                        let (elems, close) = parse_tuple(tokens, tok, e, parse_expr)?;
+                        Ok((Ast::tuple(elems, start.merge(&close)), close.1))
+                    }
+                    Some(t) => Err(ParserError::UnexpectedToken(t)),
                     _ => Err(ParserError::UnclosedOpenParen(tok)),
                 }
             }
-            TokenKind::Var(s) => Ok(Ast::var(s, tok.loc)),
+            TokenKind::Lbracket => {
+                let start = tok.loc.clone();
+                let (elems, close) = parse_seq(tokens, tok, TokenKind::Rbracket, parse_expr)?;
+                Ok((Ast::list(elems, start.merge(&close)), close.1))
+            }
+            TokenKind::Lbrace => {
+                let record = parse_record(tokens, tok)?;
+                let end = record.loc.1;
+                Ok((record, end))
+            }
+            // builtins such as `String` are capitalized
+            TokenKind::Var(s) | TokenKind::Upper(s) => {
+                Ok((Ast::var(s, tok.loc.clone()), tok.loc.1))
+            }
             _ => Err(ParserError::NotExpression(tok)),
         })
 }
 
+// record ::= "{" (field ("," field)* ","?)? "}"
+//          | "{" expr "|" field ("," field)* ","? "}"
+// parses a record literal or update once its "{" has been consumed
+fn parse_record<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+    open: Token,
+) -> Result<Ast, ParserError> {
+    let start = open.loc.clone();
+    tokens.reset_peek();
+    let peeked = (
+        tokens.peek().map(|tok| tok.value.clone()),
+        tokens.peek().map(|tok| tok.value.clone()),
+    );
+    let is_literal = matches!(
+        peeked,
+        (Some(TokenKind::Rbrace), _) | (Some(TokenKind::Var(_)), Some(TokenKind::Equal))
+    );
+    tokens.reset_peek();
+    if is_literal {
+        let (fields, close) = parse_seq(tokens, open, TokenKind::Rbrace, |tokens| {
+            parse_field(tokens, TokenKind::Equal, parse_expr)
+        })?;
+        return Ok(Ast::record(fields, start.merge(&close)));
+    }
+    let record = parse_expr(tokens)?;
+    expect_token(tokens, TokenKind::Bar)?;
+    let (fields, close) = parse_seq(tokens, open, TokenKind::Rbrace, |tokens| {
+        parse_field(tokens, TokenKind::Equal, parse_expr)
+    })?;
+    // `{ r | }` updates nothing
+    if fields.is_empty() {
+        return Err(ParserError::UnexpectedToken(Token::rbrace(close)));
+    }
+    Ok(Ast::update(record, fields, start.merge(&close)))
+}
+
+// field ::= var sep item
+fn parse_field<Tokens: Iterator<Item = Token>, T>(
+    tokens: &mut MultiPeek<Tokens>,
+    sep: TokenKind,
+    item: impl FnOnce(&mut MultiPeek<Tokens>) -> Result<T, ParserError>,
+) -> Result<(String, T), ParserError> {
+    let name = match tokens.next() {
+        Some(Token {
+            value: TokenKind::Var(name),
+            ..
+        }) => name,
+        Some(t) => return Err(ParserError::UnexpectedToken(t)),
+        None => return Err(ParserError::Eof),
+    };
+    expect_token(tokens, sep)?;
+    Ok((name, item(tokens)?))
+}
+
+// parses `(item ("," item)* ","?)? close` once `open` has been consumed,
+// returning the items and the location of `close`
+fn parse_seq<Tokens: Iterator<Item = Token>, T>(
+    tokens: &mut MultiPeek<Tokens>,
+    open: Token,
+    close: TokenKind,
+    mut item: impl FnMut(&mut MultiPeek<Tokens>) -> Result<T, ParserError>,
+) -> Result<(Vec<T>, Loc), ParserError> {
+    let mut items = Vec::new();
+    loop {
+        tokens.reset_peek();
+        match tokens.peek().map(|tok| tok.value.clone()) {
+            Some(ref kind) if *kind == close => return Ok((items, tokens.next().unwrap().loc)),
+            None => return Err(ParserError::UnclosedOpenParen(open)),
+            _ => (),
+        }
+        items.push(item(tokens)?);
+        match tokens.next() {
+            Some(Token {
+                value: TokenKind::Comma,
+                ..
+            }) => (),
+            Some(tok) if tok.value == close => return Ok((items, tok.loc)),
+            Some(tok) => return Err(ParserError::UnexpectedToken(tok)),
+            None => return Err(ParserError::UnclosedOpenParen(open)),
+        }
+    }
+}
+
+// parses the elements after the first of a tuple `(first, item, ...)`
+// once the comma following `first` has been consumed
+fn parse_tuple<Tokens: Iterator<Item = Token>, T>(
+    tokens: &mut MultiPeek<Tokens>,
+    open: Token,
+    first: T,
+    item: impl FnMut(&mut MultiPeek<Tokens>) -> Result<T, ParserError>,
+) -> Result<(Vec<T>, Loc), ParserError> {
+    let (mut items, close) = parse_seq(tokens, open, TokenKind::Rparen, item)?;
+    // `(a,)` is not a tuple
+    if items.is_empty() {
+        return Err(ParserError::UnexpectedToken(Token::rparen(close)));
+    }
+    items.insert(0, first);
+    Ok((items, close))
+}
+
+// type_def ::= "type" upper "=" (type | variant ("|" variant)*
+//            | indent variant (newline+ variant)* dedent)
+fn parse_type_def<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<Ast, ParserError> {
+    let start = tokens.next().unwrap().loc;
+    let name = match tokens.next() {
+        Some(Token {
+            value: TokenKind::Upper(name),
+            ..
+        }) => name,
+        Some(t) => return Err(ParserError::UnexpectedToken(t)),
+        None => return Err(ParserError::Eof),
+    };
+    expect_token(tokens, TokenKind::Equal)?;
+    if !is_sum_type(tokens) {
+        let ty = parse_type(tokens)?;
+        let loc = start.merge(&ty.loc);
+        return Ok(Ast::type_alias(name, ty, loc));
+    }
+    let mut variants = Vec::new();
+    let mut end;
+    tokens.reset_peek();
+    if let Some(TokenKind::Indent) = tokens.peek().map(|tok| tok.value.clone()) {
+        tokens.next();
+        loop {
+            let (variant, loc) = parse_variant(tokens)?;
+            variants.push(variant);
+            end = loc;
+            match tokens.next() {
+                Some(Token {
+                    value: TokenKind::Newline,
+                    ..
+                }) => skip_newlines(tokens),
+                Some(Token {
+                    value: TokenKind::Dedent,
+                    ..
+                }) => break,
+                Some(t) => return Err(ParserError::RedundantExpression(t)),
+                None => return Err(ParserError::Eof),
+            }
+        }
+    } else {
+        loop {
+            let (variant, loc) = parse_variant(tokens)?;
+            variants.push(variant);
+            end = loc;
+            tokens.reset_peek();
+            if tokens.peek().map(|tok| tok.value.clone()) != Some(TokenKind::Bar) {
+                break;
+            }
+        }
+    }
+    Ok(Ast::data(name, variants, start.merge(&end)))
+}
+
+// whether the right hand side of `type Name =` is a sum type, i.e. it has a
+// "|" or a constructor with fields such as `Circle Int`. `[String]` and
+// `Int -> Int` are aliases.
+fn is_sum_type<Tokens: Iterator<Item = Token>>(tokens: &mut MultiPeek<Tokens>) -> bool {
+    tokens.reset_peek();
+    let mut depth = 0;
+    let mut atoms = 0;
+    let sum = loop {
+        match tokens.peek().map(|tok| tok.value.clone()) {
+            Some(TokenKind::Bar) | Some(TokenKind::Indent) if depth == 0 => break true,
+            Some(TokenKind::Arrow) if depth == 0 => break false,
+            Some(TokenKind::Lparen) | Some(TokenKind::Lbracket) | Some(TokenKind::Lbrace) => {
+                if depth == 0 {
+                    atoms += 1;
+                }
+                depth += 1;
+            }
+            Some(TokenKind::Rparen) | Some(TokenKind::Rbracket) | Some(TokenKind::Rbrace) => {
+                depth -= 1
+            }
+            Some(TokenKind::Var(_)) | Some(TokenKind::Upper(_)) if depth == 0 => atoms += 1,
+            Some(_) if depth > 0 => (),
+            _ => break atoms > 1,
+        }
+    };
+    tokens.reset_peek();
+    sum
+}
+
+// variant ::= "|"? upper type_atom*
+// returns the variant along with its location
+fn parse_variant<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<(Variant, Loc), ParserError> {
+    tokens.reset_peek();
+    if let Some(TokenKind::Bar) = tokens.peek().map(|tok| tok.value.clone()) {
+        tokens.next();
+    }
+    let (name, mut loc) = match tokens.next() {
+        Some(Token {
+            value: TokenKind::Upper(name),
+            loc,
+        }) => (name, loc),
+        Some(t) => return Err(ParserError::UnexpectedToken(t)),
+        None => return Err(ParserError::Eof),
+    };
+    let mut fields = Vec::new();
+    loop {
+        tokens.reset_peek();
+        match tokens.peek().map(|tok| tok.value.clone()) {
+            Some(TokenKind::Var(_))
+            | Some(TokenKind::Upper(_))
+            | Some(TokenKind::Lbracket)
+            | Some(TokenKind::Lparen)
+            | Some(TokenKind::Lbrace) => {
+                let field = parse_type_atom(tokens)?;
+                loc = loc.merge(&field.loc);
+                fields.push(field);
+            }
+            _ => return Ok((Variant::new(name, fields), loc)),
+        }
+    }
+}
+
+// type ::= type_atom+ ("->" type)?
+// `A B -> C` is a function taking an `A` and a `B`, i.e. `A -> B -> C`
+fn parse_type<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<Type, ParserError> {
+    let mut args = vec![parse_type_atom(tokens)?];
+    loop {
+        tokens.reset_peek();
+        match tokens.peek().map(|tok| tok.value.clone()) {
+            Some(TokenKind::Var(_))
+            | Some(TokenKind::Upper(_))
+            | Some(TokenKind::Lbracket)
+            | Some(TokenKind::Lparen)
+            | Some(TokenKind::Lbrace) => args.push(parse_type_atom(tokens)?),
+            Some(TokenKind::Arrow) => {
+                tokens.next();
+                let ret = parse_type(tokens)?;
+                return Ok(args.into_iter().rev().fold(ret, |ret, arg| {
+                    let loc = arg.loc.merge(&ret.loc);
+                    Type::fun(arg, ret, loc)
+                }));
+            }
+            _ if args.len() == 1 => return Ok(args.pop().unwrap()),
+            _ => {
+                tokens.reset_peek();
+                return Err(tokens
+                    .next()
+                    .map(ParserError::UnexpectedToken)
+                    .unwrap_or(ParserError::Eof));
+            }
+        }
+    }
+}
+
+// type_atom ::= upper | var | "[" type "]" | "(" type ")"
+//             | "{" (var ":" type ("," var ":" type)* ","?)? "}"
+fn parse_type_atom<Tokens: Iterator<Item = Token>>(
+    tokens: &mut MultiPeek<Tokens>,
+) -> Result<Type, ParserError> {
+    tokens
+        .next()
+        .ok_or(ParserError::Eof)
+        .and_then(|tok| match tok.value {
+            TokenKind::Var(s) => Ok(Type::var(s, tok.loc)),
+            TokenKind::Upper(s) => Ok(Type::name(s, tok.loc)),
+            TokenKind::Lbracket => {
+                let elem = parse_type(tokens)?;
+                match tokens.next() {
+                    Some(Token {
+                        value: TokenKind::Rbracket,
+                        loc,
+                    }) => {
+                        let loc = tok.loc.merge(&loc);
+                        Ok(Type::list(elem, loc))
+                    }
+                    Some(t) => Err(ParserError::UnexpectedToken(t)),
+                    _ => Err(ParserError::UnclosedOpenParen(tok)),
+                }
+            }
+            TokenKind::Lbrace => {
+                let start = tok.loc.clone();
+                let (fields, close) = parse_seq(tokens, tok, TokenKind::Rbrace, |tokens| {
+                    parse_field(tokens, TokenKind::Colon, parse_type)
+                })?;
+                Ok(Type::record(fields, start.merge(&close)))
+            }
+            TokenKind::Lparen => {
+                let ty = parse_type(tokens)?;
+                match tokens.next() {
+                    Some(Token {
+                        value: TokenKind::Rparen,
+                        ..
+                    }) => Ok(ty),
+                    Some(Token {
+                        value: TokenKind::Comma,
+                        ..
+                    }) => {
+                        let start = tok.loc.clone();
+                        let (elems, close) = parse_tuple(tokens, tok, ty, parse_type)?;
+                        Ok(Type::tuple(elems, start.merge(&close)))
+                    }
+                    Some(t) => Err(ParserError::UnexpectedToken(t)),
+                    _ => Err(ParserError::UnclosedOpenParen(tok)),
+                }
+            }
+            _ => Err(ParserError::NotType(tok)),
+        })
+}
+
 #[test]
 fn test_parse_binop_parens() {
-    use crate::lexer::{Loc, Token};
-
     // "12 + (3 - 123) * 3 / 4",
     let ast = parser(vec![
         Token::number(12, Loc(0, 2)),
@@ -179,25 +1227,28 @@ fn test_parse_binop_parens() {
 
     assert_eq!(
         ast,
-        Ok(Ast::binop(
-            BinOp::add(Loc(3, 4)),
-            Ast::num(12, Loc(0, 2)),
-            Ast::binop(
-                BinOp::div(Loc(19, 20)),
+        Ok(Ast::program(
+            vec![Ast::binop(
+                BinOp::add(Loc(3, 4)),
+                Ast::num(12, Loc(0, 2)),
                 Ast::binop(
-                    BinOp::mul(Loc(15, 16)),
+                    BinOp::div(Loc(19, 20)),
                     Ast::binop(
-                        BinOp::sub(Loc(8, 9)),
-                        Ast::num(3, Loc(6, 7)),
-                        Ast::num(123, Loc(10, 13)),
-                        Loc(6, 13)
+                        BinOp::mul(Loc(15, 16)),
+                        Ast::binop(
+                            BinOp::sub(Loc(8, 9)),
+                            Ast::num(3, Loc(6, 7)),
+                            Ast::num(123, Loc(10, 13)),
+                            Loc(6, 13)
+                        ),
+                        Ast::num(3, Loc(17, 18)),
+                        Loc(6, 18)
                     ),
-                    Ast::num(3, Loc(17, 18)),
-                    Loc(6, 18)
+                    Ast::num(4, Loc(21, 22)),
+                    Loc(6, 22)
                 ),
-                Ast::num(4, Loc(21, 22)),
-                Loc(6, 22)
-            ),
+                Loc(0, 22)
+            )],
             Loc(0, 22)
         ))
     );
@@ -205,8 +1256,6 @@ fn test_parse_binop_parens() {
 
 #[test]
 fn test_parse_uniop() {
-    use crate::lexer::{Loc, Token};
-
     // "-2+(+3)"
     let ast = parser(vec![
         Token::minus(Loc(0, 1)),
@@ -220,19 +1269,20 @@ fn test_parse_uniop() {
 
     assert_eq!(
         ast,
-        Ok(Ast::binop(
-            BinOp::add(Loc(2, 3)),
-            Ast::uniop(UniOp::minus(Loc(0, 1)), Ast::num(2, Loc(1, 2)), Loc(1, 2)),
-            Ast::uniop(UniOp::plus(Loc(4, 5)), Ast::num(3, Loc(5, 6)), Loc(5, 6)),
-            Loc(1, 6)
+        Ok(Ast::program(
+            vec![Ast::binop(
+                BinOp::add(Loc(2, 3)),
+                Ast::uniop(UniOp::minus(Loc(0, 1)), Ast::num(2, Loc(1, 2)), Loc(0, 2)),
+                Ast::uniop(UniOp::plus(Loc(4, 5)), Ast::num(3, Loc(5, 6)), Loc(4, 6)),
+                Loc(0, 6)
+            )],
+            Loc(0, 6)
         ))
     );
 }
 
 #[test]
 fn test_parse_bind() {
-    use crate::lexer::{Loc, Token};
-
     // "hoge := 40 + 2"
     let ast = parser(vec![
         Token::var("hoge", Loc(0, 4)),
@@ -244,14 +1294,17 @@ fn test_parse_bind() {
 
     assert_eq!(
         ast,
-        Ok(Ast::bind(
-            "hoge".to_string(),
-            Box::new(Ast::binop(
-                BinOp::add(Loc(11, 12)),
-                Ast::num(40, Loc(8, 10)),
-                Ast::num(2, Loc(13, 14)),
-                Loc(8, 14)
-            )),
+        Ok(Ast::program(
+            vec![Ast::bind(
+                "hoge".to_string(),
+                Box::new(Ast::binop(
+                    BinOp::add(Loc(11, 12)),
+                    Ast::num(40, Loc(8, 10)),
+                    Ast::num(2, Loc(13, 14)),
+                    Loc(8, 14)
+                )),
+                Loc(0, 14)
+            )],
             Loc(0, 14)
         ))
     );
@@ -259,8 +1312,6 @@ fn test_parse_bind() {
 
 #[test]
 fn test_parse_var_var() {
-    use crate::lexer::{Loc, Token};
-
     // "x + x"
     let ast = parser(vec![
         Token::var("x", Loc(0, 1)),
@@ -270,10 +1321,13 @@ fn test_parse_var_var() {
 
     assert_eq!(
         ast,
-        Ok(Ast::binop(
-            BinOp::add(Loc(2, 3)),
-            Ast::var("x".to_string(), Loc(0, 1)),
-            Ast::var("x".to_string(), Loc(4, 5)),
+        Ok(Ast::program(
+            vec![Ast::binop(
+                BinOp::add(Loc(2, 3)),
+                Ast::var("x".to_string(), Loc(0, 1)),
+                Ast::var("x".to_string(), Loc(4, 5)),
+                Loc(0, 5)
+            )],
             Loc(0, 5)
         ))
     );
@@ -281,8 +1335,6 @@ fn test_parse_var_var() {
 
 #[test]
 fn test_parse_same_symbol() {
-    use crate::lexer::{Loc, Token};
-
     // "1 + 2 + 3"
     let ast = parser(vec![
         Token::number(1, Loc(0, 1)),
@@ -294,16 +1346,1106 @@ fn test_parse_same_symbol() {
 
     assert_eq!(
         ast,
-        Ok(Ast::binop(
-            BinOp::add(Loc(6, 7)),
-            Ast::binop(
-                BinOp::add(Loc(2, 3)),
-                Ast::num(1, Loc(0, 1)),
-                Ast::num(2, Loc(4, 5)),
-                Loc(0, 5)
-            ),
-            Ast::num(3, Loc(8, 9)),
+        Ok(Ast::program(
+            vec![Ast::binop(
+                BinOp::add(Loc(6, 7)),
+                Ast::binop(
+                    BinOp::add(Loc(2, 3)),
+                    Ast::num(1, Loc(0, 1)),
+                    Ast::num(2, Loc(4, 5)),
+                    Loc(0, 5)
+                ),
+                Ast::num(3, Loc(8, 9)),
+                Loc(0, 9)
+            )],
             Loc(0, 9)
         ))
     );
 }
+
+#[test]
+fn test_parse_program() {
+    // "hoge := 1\n\nhoge + 2\n"
+    let ast = parser(vec![
+        Token::var("hoge", Loc(0, 4)),
+        Token::bind(Loc(5, 7)),
+        Token::number(1, Loc(8, 9)),
+        Token::newline(Loc(9, 10)),
+        Token::newline(Loc(10, 11)),
+        Token::var("hoge", Loc(11, 15)),
+        Token::plus(Loc(16, 17)),
+        Token::number(2, Loc(18, 19)),
+        Token::newline(Loc(19, 20)),
+    ]);
+
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![
+                Ast::bind(
+                    "hoge".to_string(),
+                    Box::new(Ast::num(1, Loc(8, 9))),
+                    Loc(0, 9)
+                ),
+                Ast::binop(
+                    BinOp::add(Loc(16, 17)),
+                    Ast::var("hoge".to_string(), Loc(11, 15)),
+                    Ast::num(2, Loc(18, 19)),
+                    Loc(11, 19)
+                ),
+            ],
+            Loc(0, 19)
+        ))
+    );
+}
+
+#[test]
+fn test_parse_program_redundant() {
+    // "1 )"
+    let ast = parser(vec![Token::number(1, Loc(0, 1)), Token::rparen(Loc(2, 3))]);
+
+    assert_eq!(
+        ast,
+        Err(ParserError::RedundantExpression(Token::rparen(Loc(2, 3))))
+    );
+}
+
+#[test]
+fn test_parse_type_decl() {
+    // "mod ::= Int [Int] -> Int"
+    let ast = parser(vec![
+        Token::var("mod", Loc(0, 3)),
+        Token::type_bind(Loc(4, 7)),
+        Token::upper("Int", Loc(8, 11)),
+        Token::lbracket(Loc(12, 13)),
+        Token::upper("Int", Loc(13, 16)),
+        Token::rbracket(Loc(16, 17)),
+        Token::arrow(Loc(18, 20)),
+        Token::upper("Int", Loc(21, 24)),
+    ]);
+
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::type_decl(
+                "mod".to_string(),
+                Type::fun(
+                    Type::name("Int", Loc(8, 11)),
+                    Type::fun(
+                        Type::list(Type::name("Int", Loc(13, 16)), Loc(12, 17)),
+                        Type::name("Int", Loc(21, 24)),
+                        Loc(12, 24)
+                    ),
+                    Loc(8, 24)
+                ),
+                Loc(0, 24)
+            )],
+            Loc(0, 24)
+        ))
+    );
+}
+
+#[test]
+fn test_parse_type_var() {
+    // "id ::= a -> [a]"
+    let ast = parser(vec![
+        Token::var("id", Loc(0, 2)),
+        Token::type_bind(Loc(3, 6)),
+        Token::var("a", Loc(7, 8)),
+        Token::arrow(Loc(9, 11)),
+        Token::lbracket(Loc(12, 13)),
+        Token::var("a", Loc(13, 14)),
+        Token::rbracket(Loc(14, 15)),
+    ]);
+
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::type_decl(
+                "id".to_string(),
+                Type::fun(
+                    Type::var("a", Loc(7, 8)),
+                    Type::list(Type::var("a", Loc(13, 14)), Loc(12, 15)),
+                    Loc(7, 15)
+                ),
+                Loc(0, 15)
+            )],
+            Loc(0, 15)
+        ))
+    );
+}
+
+#[test]
+fn test_parse_lambda() {
+    // "f := x n => g x n"
+    let ast = parser(vec![
+        Token::var("f", Loc(0, 1)),
+        Token::bind(Loc(2, 4)),
+        Token::var("x", Loc(5, 6)),
+        Token::var("n", Loc(7, 8)),
+        Token::fat_arrow(Loc(9, 11)),
+        Token::var("g", Loc(12, 13)),
+        Token::var("x", Loc(14, 15)),
+        Token::var("n", Loc(16, 17)),
+    ]);
+
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::bind(
+                "f".to_string(),
+                Box::new(Ast::lambda(
+                    "x".to_string(),
+                    Ast::lambda(
+                        "n".to_string(),
+                        Ast::app(
+                            Ast::app(
+                                Ast::var("g".to_string(), Loc(12, 13)),
+                                Ast::var("x".to_string(), Loc(14, 15)),
+                                Loc(12, 15)
+                            ),
+                            Ast::var("n".to_string(), Loc(16, 17)),
+                            Loc(12, 17)
+                        ),
+                        Loc(7, 17)
+                    ),
+                    Loc(5, 17)
+                )),
+                Loc(0, 17)
+            )],
+            Loc(0, 17)
+        ))
+    );
+}
+
+#[test]
+fn test_parse_app_precedence() {
+    // "-f 1 * 2"
+    let ast = parser(vec![
+        Token::minus(Loc(0, 1)),
+        Token::var("f", Loc(1, 2)),
+        Token::number(1, Loc(3, 4)),
+        Token::asterisk(Loc(5, 6)),
+        Token::number(2, Loc(7, 8)),
+    ]);
+
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::binop(
+                BinOp::mul(Loc(5, 6)),
+                Ast::uniop(
+                    UniOp::minus(Loc(0, 1)),
+                    Ast::app(
+                        Ast::var("f".to_string(), Loc(1, 2)),
+                        Ast::num(1, Loc(3, 4)),
+                        Loc(1, 4)
+                    ),
+                    Loc(0, 4)
+                ),
+                Ast::num(2, Loc(7, 8)),
+                Loc(0, 8)
+            )],
+            Loc(0, 8)
+        ))
+    );
+}
+
+#[test]
+fn test_parse_pow() {
+    // "-2 ^ 3 ^ 2 % 5"
+    let ast = parser(vec![
+        Token::minus(Loc(0, 1)),
+        Token::number(2, Loc(1, 2)),
+        Token::caret(Loc(3, 4)),
+        Token::number(3, Loc(5, 6)),
+        Token::caret(Loc(7, 8)),
+        Token::number(2, Loc(9, 10)),
+        Token::percent(Loc(11, 12)),
+        Token::number(5, Loc(13, 14)),
+    ]);
+
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::binop(
+                BinOp::modulo(Loc(11, 12)),
+                Ast::uniop(
+                    UniOp::minus(Loc(0, 1)),
+                    Ast::binop(
+                        BinOp::pow(Loc(3, 4)),
+                        Ast::num(2, Loc(1, 2)),
+                        Ast::binop(
+                            BinOp::pow(Loc(7, 8)),
+                            Ast::num(3, Loc(5, 6)),
+                            Ast::num(2, Loc(9, 10)),
+                            Loc(5, 10)
+                        ),
+                        Loc(1, 10)
+                    ),
+                    Loc(0, 10)
+                ),
+                Ast::num(5, Loc(13, 14)),
+                Loc(0, 14)
+            )],
+            Loc(0, 14)
+        ))
+    );
+}
+
+#[test]
+fn test_parse_concat() {
+    // "\"n\" ++ String n ++ \"!\""
+    let ast = parser(vec![
+        Token::str("n", Loc(0, 3)),
+        Token::plus_plus(Loc(4, 6)),
+        Token::upper("String", Loc(7, 13)),
+        Token::var("n", Loc(14, 15)),
+        Token::plus_plus(Loc(16, 18)),
+        Token::str("!", Loc(19, 22)),
+    ]);
+
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::binop(
+                BinOp::concat(Loc(16, 18)),
+                Ast::binop(
+                    BinOp::concat(Loc(4, 6)),
+                    Ast::str("n", Loc(0, 3)),
+                    Ast::app(
+                        Ast::var("String".to_string(), Loc(7, 13)),
+                        Ast::var("n".to_string(), Loc(14, 15)),
+                        Loc(7, 15)
+                    ),
+                    Loc(0, 15)
+                ),
+                Ast::str("!", Loc(19, 22)),
+                Loc(0, 22)
+            )],
+            Loc(0, 22)
+        ))
+    );
+}
+
+#[test]
+fn test_parse_match() {
+    // "match n\n  | 0 => x\n  -1 => \"m\"\n  | _ => n"
+    let ast = parser(vec![
+        Token::match_(Loc(0, 5)),
+        Token::var("n", Loc(6, 7)),
+        Token::indent(Loc(8, 10)),
+        Token::bar(Loc(10, 11)),
+        Token::number(0, Loc(12, 13)),
+        Token::fat_arrow(Loc(14, 16)),
+        Token::var("x", Loc(17, 18)),
+        Token::newline(Loc(18, 19)),
+        Token::minus(Loc(21, 22)),
+        Token::number(1, Loc(22, 23)),
+        Token::fat_arrow(Loc(24, 26)),
+        Token::str("m", Loc(27, 30)),
+        Token::newline(Loc(30, 31)),
+        Token::bar(Loc(33, 34)),
+        Token::underscore(Loc(35, 36)),
+        Token::fat_arrow(Loc(37, 39)),
+        Token::var("n", Loc(40, 41)),
+        Token::dedent(Loc(41, 41)),
+    ]);
+
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::match_(
+                Ast::var("n".to_string(), Loc(6, 7)),
+                vec![
+                    Arm::new(
+                        Pattern::num(0, Loc(12, 13)),
+                        Ast::var("x".to_string(), Loc(17, 18))
+                    ),
+                    Arm::new(Pattern::num(-1, Loc(21, 23)), Ast::str("m", Loc(27, 30))),
+                    Arm::new(
+                        Pattern::wildcard(Loc(35, 36)),
+                        Ast::var("n".to_string(), Loc(40, 41))
+                    ),
+                ],
+                Loc(0, 41)
+            )],
+            Loc(0, 41)
+        ))
+    );
+
+    // "match n | 0 => 1 | _ => 2"
+    let ast = parser(vec![
+        Token::match_(Loc(0, 5)),
+        Token::var("n", Loc(6, 7)),
+        Token::bar(Loc(8, 9)),
+        Token::number(0, Loc(10, 11)),
+        Token::fat_arrow(Loc(12, 14)),
+        Token::number(1, Loc(15, 16)),
+        Token::bar(Loc(17, 18)),
+        Token::underscore(Loc(19, 20)),
+        Token::fat_arrow(Loc(21, 23)),
+        Token::number(2, Loc(24, 25)),
+    ]);
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::match_(
+                Ast::var("n".to_string(), Loc(6, 7)),
+                vec![
+                    Arm::new(Pattern::num(0, Loc(10, 11)), Ast::num(1, Loc(15, 16))),
+                    Arm::new(Pattern::wildcard(Loc(19, 20)), Ast::num(2, Loc(24, 25))),
+                ],
+                Loc(0, 25)
+            )],
+            Loc(0, 25)
+        ))
+    );
+
+    // "match n\n1"
+    let ast = parser(vec![
+        Token::match_(Loc(0, 5)),
+        Token::var("n", Loc(6, 7)),
+        Token::newline(Loc(7, 8)),
+        Token::number(1, Loc(8, 9)),
+    ]);
+    assert_eq!(
+        ast,
+        Err(ParserError::UnexpectedToken(Token::newline(Loc(7, 8))))
+    );
+}
+
+#[test]
+fn test_parse_guard() {
+    // "match n | x if b => x | p y => y"
+    let ast = parser(vec![
+        Token::match_(Loc(0, 5)),
+        Token::var("n", Loc(6, 7)),
+        Token::bar(Loc(8, 9)),
+        Token::var("x", Loc(10, 11)),
+        Token::if_(Loc(12, 14)),
+        Token::var("b", Loc(15, 16)),
+        Token::fat_arrow(Loc(17, 19)),
+        Token::var("x", Loc(20, 21)),
+        Token::bar(Loc(22, 23)),
+        Token::var("p", Loc(24, 25)),
+        Token::var("y", Loc(26, 27)),
+        Token::fat_arrow(Loc(28, 30)),
+        Token::var("y", Loc(31, 32)),
+    ]);
+
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::match_(
+                Ast::var("n".to_string(), Loc(6, 7)),
+                vec![
+                    Arm::guarded(
+                        Pattern::var("x", Loc(10, 11)),
+                        Ast::var("b".to_string(), Loc(15, 16)),
+                        Ast::var("x".to_string(), Loc(20, 21))
+                    ),
+                    // the predicate shorthand
+                    Arm::guarded(
+                        Pattern::var("y", Loc(26, 27)),
+                        Ast::app(
+                            Ast::var("p".to_string(), Loc(24, 25)),
+                            Ast::var("y".to_string(), Loc(26, 27)),
+                            Loc(24, 27)
+                        ),
+                        Ast::var("y".to_string(), Loc(31, 32))
+                    ),
+                ],
+                Loc(0, 32)
+            )],
+            Loc(0, 32)
+        ))
+    );
+}
+
+#[test]
+fn test_parse_logic_precedence() {
+    // "!a || b && c < d ++ e"
+    let ast = parser(vec![
+        Token::bang(Loc(0, 1)),
+        Token::var("a", Loc(1, 2)),
+        Token::or_or(Loc(3, 5)),
+        Token::var("b", Loc(6, 7)),
+        Token::and_and(Loc(8, 10)),
+        Token::var("c", Loc(11, 12)),
+        Token::lt(Loc(13, 14)),
+        Token::var("d", Loc(15, 16)),
+        Token::plus_plus(Loc(17, 19)),
+        Token::var("e", Loc(20, 21)),
+    ]);
+
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::binop(
+                BinOp::or(Loc(3, 5)),
+                Ast::uniop(
+                    UniOp::not(Loc(0, 1)),
+                    Ast::var("a".to_string(), Loc(1, 2)),
+                    Loc(0, 2)
+                ),
+                Ast::binop(
+                    BinOp::and(Loc(8, 10)),
+                    Ast::var("b".to_string(), Loc(6, 7)),
+                    Ast::binop(
+                        BinOp::lt(Loc(13, 14)),
+                        Ast::var("c".to_string(), Loc(11, 12)),
+                        Ast::binop(
+                            BinOp::concat(Loc(17, 19)),
+                            Ast::var("d".to_string(), Loc(15, 16)),
+                            Ast::var("e".to_string(), Loc(20, 21)),
+                            Loc(15, 21)
+                        ),
+                        Loc(11, 21)
+                    ),
+                    Loc(6, 21)
+                ),
+                Loc(0, 21)
+            )],
+            Loc(0, 21)
+        ))
+    );
+
+    // "a < b < c"
+    let ast = parser(vec![
+        Token::var("a", Loc(0, 1)),
+        Token::lt(Loc(2, 3)),
+        Token::var("b", Loc(4, 5)),
+        Token::lt(Loc(6, 7)),
+        Token::var("c", Loc(8, 9)),
+    ]);
+    assert_eq!(ast, Err(ParserError::UnexpectedToken(Token::lt(Loc(6, 7)))));
+}
+
+#[test]
+fn test_parse_if() {
+    // "if a then 1 else x => x"
+    let ast = parser(vec![
+        Token::if_(Loc(0, 2)),
+        Token::var("a", Loc(3, 4)),
+        Token::then(Loc(5, 9)),
+        Token::number(1, Loc(10, 11)),
+        Token::else_(Loc(12, 16)),
+        Token::var("x", Loc(17, 18)),
+        Token::fat_arrow(Loc(19, 21)),
+        Token::var("x", Loc(22, 23)),
+    ]);
+
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::if_(
+                Ast::var("a".to_string(), Loc(3, 4)),
+                Ast::num(1, Loc(10, 11)),
+                Ast::lambda(
+                    "x".to_string(),
+                    Ast::var("x".to_string(), Loc(22, 23)),
+                    Loc(17, 23)
+                ),
+                Loc(0, 23)
+            )],
+            Loc(0, 23)
+        ))
+    );
+}
+
+#[test]
+fn test_parse_block() {
+    // "f := n =>\n  m := n\n\n  m\nf"
+    let ast = parser(vec![
+        Token::var("f", Loc(0, 1)),
+        Token::bind(Loc(2, 4)),
+        Token::var("n", Loc(5, 6)),
+        Token::fat_arrow(Loc(7, 9)),
+        Token::indent(Loc(10, 12)),
+        Token::var("m", Loc(12, 13)),
+        Token::bind(Loc(14, 16)),
+        Token::var("n", Loc(17, 18)),
+        Token::newline(Loc(18, 19)),
+        Token::newline(Loc(19, 20)),
+        Token::var("m", Loc(22, 23)),
+        Token::dedent(Loc(24, 24)),
+        Token::newline(Loc(23, 24)),
+        Token::var("f", Loc(24, 25)),
+    ]);
+
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![
+                Ast::bind(
+                    "f".to_string(),
+                    Box::new(Ast::lambda(
+                        "n".to_string(),
+                        Ast::block(
+                            vec![
+                                Ast::bind(
+                                    "m".to_string(),
+                                    Box::new(Ast::var("n".to_string(), Loc(17, 18))),
+                                    Loc(12, 18)
+                                ),
+                                Ast::var("m".to_string(), Loc(22, 23)),
+                            ],
+                            Loc(12, 23)
+                        ),
+                        Loc(5, 23)
+                    )),
+                    Loc(0, 23)
+                ),
+                Ast::var("f".to_string(), Loc(24, 25)),
+            ],
+            Loc(0, 25)
+        ))
+    );
+}
+
+#[test]
+fn test_parse_doc_comment() {
+    // "/// one\n\n/// two\nx := 1"
+    let ast = parser(vec![
+        Token::doc_comment("one", Loc(0, 7)),
+        Token::newline(Loc(7, 8)),
+        Token::newline(Loc(8, 9)),
+        Token::doc_comment("two", Loc(9, 16)),
+        Token::newline(Loc(16, 17)),
+        Token::var("x", Loc(17, 18)),
+        Token::bind(Loc(19, 21)),
+        Token::number(1, Loc(22, 23)),
+    ]);
+
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::bind(
+                "x".to_string(),
+                Box::new(Ast::num(1, Loc(22, 23))),
+                Loc(17, 23)
+            )
+            .with_doc(Some("one\ntwo".to_string()))],
+            Loc(17, 23)
+        ))
+    );
+
+    // "x\n/// end"
+    let ast = parser(vec![
+        Token::var("x", Loc(0, 1)),
+        Token::newline(Loc(1, 2)),
+        Token::doc_comment("end", Loc(2, 9)),
+    ]);
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::var("x".to_string(), Loc(0, 1))],
+            Loc(0, 1)
+        ))
+    );
+
+    // "f :=\n    x\n    /// end\nf"
+    let ast = parser(vec![
+        Token::var("f", Loc(0, 1)),
+        Token::bind(Loc(2, 4)),
+        Token::indent(Loc(5, 9)),
+        Token::var("x", Loc(9, 10)),
+        Token::newline(Loc(10, 11)),
+        Token::doc_comment("end", Loc(15, 22)),
+        Token::dedent(Loc(23, 23)),
+        Token::newline(Loc(22, 23)),
+        Token::var("f", Loc(23, 24)),
+    ]);
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![
+                Ast::bind(
+                    "f".to_string(),
+                    Box::new(Ast::block(
+                        vec![Ast::var("x".to_string(), Loc(9, 10))],
+                        Loc(9, 10)
+                    )),
+                    Loc(0, 10)
+                ),
+                Ast::var("f".to_string(), Loc(23, 24)),
+            ],
+            Loc(0, 24)
+        ))
+    );
+
+    // "f :=\n    /// end\nf"
+    let ast = parser(vec![
+        Token::var("f", Loc(0, 1)),
+        Token::bind(Loc(2, 4)),
+        Token::indent(Loc(5, 9)),
+        Token::doc_comment("end", Loc(9, 16)),
+        Token::dedent(Loc(17, 17)),
+        Token::newline(Loc(16, 17)),
+        Token::var("f", Loc(17, 18)),
+    ]);
+    assert_eq!(
+        ast,
+        Err(ParserError::NotExpression(Token::dedent(Loc(17, 17))))
+    );
+    // without a doc comment a statement is still required
+    let ast = parser(vec![
+        Token::var("f", Loc(0, 1)),
+        Token::bind(Loc(2, 4)),
+        Token::indent(Loc(5, 9)),
+        Token::var("x", Loc(9, 10)),
+        Token::newline(Loc(10, 11)),
+        Token::dedent(Loc(11, 11)),
+    ]);
+    assert_eq!(
+        ast,
+        Err(ParserError::NotExpression(Token::dedent(Loc(11, 11))))
+    );
+}
+
+#[test]
+fn test_parse_fn() {
+    // "/// d\nfn f x := Int -> Int => x"
+    let ast = parser(vec![
+        Token::doc_comment("d", Loc(0, 5)),
+        Token::newline(Loc(5, 6)),
+        Token::fn_(Loc(6, 8)),
+        Token::var("f", Loc(9, 10)),
+        Token::var("x", Loc(11, 12)),
+        Token::bind(Loc(13, 15)),
+        Token::upper("Int", Loc(16, 19)),
+        Token::arrow(Loc(20, 22)),
+        Token::upper("Int", Loc(23, 26)),
+        Token::fat_arrow(Loc(27, 29)),
+        Token::var("x", Loc(30, 31)),
+    ]);
+
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![
+                Ast::type_decl(
+                    "f".to_string(),
+                    Type::fun(
+                        Type::name("Int", Loc(16, 19)),
+                        Type::name("Int", Loc(23, 26)),
+                        Loc(16, 26)
+                    ),
+                    Loc(6, 26)
+                )
+                .with_doc(Some("d".to_string())),
+                Ast::bind(
+                    "f".to_string(),
+                    Box::new(Ast::lambda(
+                        "x".to_string(),
+                        Ast::var("x".to_string(), Loc(30, 31)),
+                        Loc(11, 31)
+                    )),
+                    Loc(6, 31)
+                ),
+            ],
+            Loc(6, 31)
+        ))
+    );
+
+    // "fn f x\n    x" has neither `=>` nor a signature
+    let ast = parser(vec![
+        Token::fn_(Loc(0, 2)),
+        Token::var("f", Loc(3, 4)),
+        Token::var("x", Loc(5, 6)),
+        Token::indent(Loc(7, 11)),
+        Token::var("x", Loc(11, 12)),
+        Token::dedent(Loc(12, 12)),
+    ]);
+    assert_eq!(
+        ast,
+        Err(ParserError::UnexpectedToken(Token::indent(Loc(7, 11))))
+    );
+
+    // "fn f => 1" takes no parameters
+    let ast = parser(vec![
+        Token::fn_(Loc(0, 2)),
+        Token::var("f", Loc(3, 4)),
+        Token::fat_arrow(Loc(5, 7)),
+        Token::number(1, Loc(8, 9)),
+    ]);
+    assert_eq!(
+        ast,
+        Err(ParserError::UnexpectedToken(Token::fat_arrow(Loc(5, 7))))
+    );
+}
+
+#[test]
+fn test_parse_list() {
+    // "f [1][0] :: xs"
+    let ast = parser(vec![
+        Token::var("f", Loc(0, 1)),
+        Token::lbracket(Loc(2, 3)),
+        Token::number(1, Loc(3, 4)),
+        Token::rbracket(Loc(4, 5)),
+        Token::lbracket(Loc(5, 6)),
+        Token::number(0, Loc(6, 7)),
+        Token::rbracket(Loc(7, 8)),
+        Token::colon_colon(Loc(9, 11)),
+        Token::var("xs", Loc(12, 14)),
+    ]);
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::binop(
+                BinOp::cons(Loc(9, 11)),
+                Ast::app(
+                    Ast::var("f".to_string(), Loc(0, 1)),
+                    Ast::index(
+                        Ast::list(vec![Ast::num(1, Loc(3, 4))], Loc(2, 5)),
+                        Ast::num(0, Loc(6, 7)),
+                        Loc(2, 8)
+                    ),
+                    Loc(0, 8)
+                ),
+                Ast::var("xs".to_string(), Loc(12, 14)),
+                Loc(0, 14)
+            )],
+            Loc(0, 14)
+        ))
+    );
+
+    // "match xs | [] => 0 | x :: _ => x"
+    let ast = parser(vec![
+        Token::match_(Loc(0, 5)),
+        Token::var("xs", Loc(6, 8)),
+        Token::bar(Loc(9, 10)),
+        Token::lbracket(Loc(11, 12)),
+        Token::rbracket(Loc(12, 13)),
+        Token::fat_arrow(Loc(14, 16)),
+        Token::number(0, Loc(17, 18)),
+        Token::bar(Loc(19, 20)),
+        Token::var("x", Loc(21, 22)),
+        Token::colon_colon(Loc(23, 25)),
+        Token::underscore(Loc(26, 27)),
+        Token::fat_arrow(Loc(28, 30)),
+        Token::var("x", Loc(31, 32)),
+    ]);
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::match_(
+                Ast::var("xs".to_string(), Loc(6, 8)),
+                vec![
+                    Arm::new(Pattern::list(vec![], Loc(11, 13)), Ast::num(0, Loc(17, 18))),
+                    Arm::new(
+                        Pattern::cons(
+                            Pattern::var("x", Loc(21, 22)),
+                            Pattern::wildcard(Loc(26, 27)),
+                            Loc(21, 27)
+                        ),
+                        Ast::var("x".to_string(), Loc(31, 32))
+                    ),
+                ],
+                Loc(0, 32)
+            )],
+            Loc(0, 32)
+        ))
+    );
+
+    // "[1, 2"
+    let ast = parser(vec![
+        Token::lbracket(Loc(0, 1)),
+        Token::number(1, Loc(1, 2)),
+        Token::comma(Loc(2, 3)),
+        Token::number(2, Loc(4, 5)),
+    ]);
+    assert_eq!(
+        ast,
+        Err(ParserError::UnclosedOpenParen(Token::lbracket(Loc(0, 1))))
+    );
+}
+
+#[test]
+fn test_parse_tuple() {
+    // "(q, r) := (1, x)"
+    let ast = parser(vec![
+        Token::lparen(Loc(0, 1)),
+        Token::var("q", Loc(1, 2)),
+        Token::comma(Loc(2, 3)),
+        Token::var("r", Loc(4, 5)),
+        Token::rparen(Loc(5, 6)),
+        Token::bind(Loc(7, 9)),
+        Token::lparen(Loc(10, 11)),
+        Token::number(1, Loc(11, 12)),
+        Token::comma(Loc(12, 13)),
+        Token::var("x", Loc(14, 15)),
+        Token::rparen(Loc(15, 16)),
+    ]);
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::destructure(
+                Pattern::tuple(
+                    vec![Pattern::var("q", Loc(1, 2)), Pattern::var("r", Loc(4, 5))],
+                    Loc(0, 6)
+                ),
+                Ast::tuple(
+                    vec![
+                        Ast::num(1, Loc(11, 12)),
+                        Ast::var("x".to_string(), Loc(14, 15))
+                    ],
+                    Loc(10, 16)
+                ),
+                Loc(0, 16)
+            )],
+            Loc(0, 16)
+        ))
+    );
+
+    // "(a, b) => a"
+    let ast = parser(vec![
+        Token::lparen(Loc(0, 1)),
+        Token::var("a", Loc(1, 2)),
+        Token::comma(Loc(2, 3)),
+        Token::var("b", Loc(4, 5)),
+        Token::rparen(Loc(5, 6)),
+        Token::fat_arrow(Loc(7, 9)),
+        Token::var("a", Loc(10, 11)),
+    ]);
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::lambda(
+                PATTERN_ARG.to_string(),
+                Ast::match_(
+                    Ast::var(PATTERN_ARG.to_string(), Loc(0, 6)),
+                    vec![Arm::new(
+                        Pattern::tuple(
+                            vec![Pattern::var("a", Loc(1, 2)), Pattern::var("b", Loc(4, 5))],
+                            Loc(0, 6)
+                        ),
+                        Ast::var("a".to_string(), Loc(10, 11))
+                    )],
+                    Loc(0, 11)
+                ),
+                Loc(0, 11)
+            )],
+            Loc(0, 11)
+        ))
+    );
+
+    // "(1,)"
+    let ast = parser(vec![
+        Token::lparen(Loc(0, 1)),
+        Token::number(1, Loc(1, 2)),
+        Token::comma(Loc(2, 3)),
+        Token::rparen(Loc(3, 4)),
+    ]);
+    assert_eq!(
+        ast,
+        Err(ParserError::UnexpectedToken(Token::rparen(Loc(3, 4))))
+    );
+}
+
+#[test]
+fn test_parse_record() {
+    // "{ r | a = r.a }"
+    let ast = parser(vec![
+        Token::lbrace(Loc(0, 1)),
+        Token::var("r", Loc(2, 3)),
+        Token::bar(Loc(4, 5)),
+        Token::var("a", Loc(6, 7)),
+        Token::equal(Loc(8, 9)),
+        Token::var("r", Loc(10, 11)),
+        Token::dot(Loc(11, 12)),
+        Token::var("a", Loc(12, 13)),
+        Token::rbrace(Loc(14, 15)),
+    ]);
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::update(
+                Ast::var("r".to_string(), Loc(2, 3)),
+                vec![(
+                    "a".to_string(),
+                    Ast::field(Ast::var("r".to_string(), Loc(10, 11)), "a", Loc(10, 13))
+                )],
+                Loc(0, 15)
+            )],
+            Loc(0, 15)
+        ))
+    );
+
+    // "match r | { a, b = 1 } => a"
+    let ast = parser(vec![
+        Token::match_(Loc(0, 5)),
+        Token::var("r", Loc(6, 7)),
+        Token::bar(Loc(8, 9)),
+        Token::lbrace(Loc(10, 11)),
+        Token::var("a", Loc(12, 13)),
+        Token::comma(Loc(13, 14)),
+        Token::var("b", Loc(15, 16)),
+        Token::equal(Loc(17, 18)),
+        Token::number(1, Loc(19, 20)),
+        Token::rbrace(Loc(21, 22)),
+        Token::fat_arrow(Loc(23, 25)),
+        Token::var("a", Loc(26, 27)),
+    ]);
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::match_(
+                Ast::var("r".to_string(), Loc(6, 7)),
+                vec![Arm::new(
+                    Pattern::record(
+                        vec![
+                            ("a".to_string(), Pattern::var("a", Loc(12, 13))),
+                            ("b".to_string(), Pattern::num(1, Loc(19, 20))),
+                        ],
+                        Loc(10, 22)
+                    ),
+                    Ast::var("a".to_string(), Loc(26, 27))
+                )],
+                Loc(0, 27)
+            )],
+            Loc(0, 27)
+        ))
+    );
+
+    // "p ::= { a : Int }"
+    let ast = parser(vec![
+        Token::var("p", Loc(0, 1)),
+        Token::type_bind(Loc(2, 5)),
+        Token::lbrace(Loc(6, 7)),
+        Token::var("a", Loc(8, 9)),
+        Token::colon(Loc(10, 11)),
+        Token::upper("Int", Loc(12, 15)),
+        Token::rbrace(Loc(16, 17)),
+    ]);
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::type_decl(
+                "p".to_string(),
+                Type::record(
+                    vec![("a".to_string(), Type::name("Int", Loc(12, 15)))],
+                    Loc(6, 17)
+                ),
+                Loc(0, 17)
+            )],
+            Loc(0, 17)
+        ))
+    );
+}
+
+#[test]
+fn test_parse_type_def() {
+    // "type T = [String]"
+    let ast = parser(vec![
+        Token::type_(Loc(0, 4)),
+        Token::upper("T", Loc(5, 6)),
+        Token::equal(Loc(7, 8)),
+        Token::lbracket(Loc(9, 10)),
+        Token::upper("String", Loc(10, 16)),
+        Token::rbracket(Loc(16, 17)),
+    ]);
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::type_alias(
+                "T".to_string(),
+                Type::list(Type::name("String", Loc(10, 16)), Loc(9, 17)),
+                Loc(0, 17)
+            )],
+            Loc(0, 17)
+        ))
+    );
+
+    // "type S = A Int | B\nmatch s | A n => n | B => 0"
+    let ast = parser(vec![
+        Token::type_(Loc(0, 4)),
+        Token::upper("S", Loc(5, 6)),
+        Token::equal(Loc(7, 8)),
+        Token::upper("A", Loc(9, 10)),
+        Token::upper("Int", Loc(11, 14)),
+        Token::bar(Loc(15, 16)),
+        Token::upper("B", Loc(17, 18)),
+        Token::newline(Loc(18, 19)),
+        Token::match_(Loc(19, 24)),
+        Token::var("s", Loc(25, 26)),
+        Token::bar(Loc(27, 28)),
+        Token::upper("A", Loc(29, 30)),
+        Token::var("n", Loc(31, 32)),
+        Token::fat_arrow(Loc(33, 35)),
+        Token::var("n", Loc(36, 37)),
+        Token::bar(Loc(38, 39)),
+        Token::upper("B", Loc(40, 41)),
+        Token::fat_arrow(Loc(42, 44)),
+        Token::number(0, Loc(45, 46)),
+    ]);
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![
+                Ast::data(
+                    "S".to_string(),
+                    vec![
+                        Variant::new("A", vec![Type::name("Int", Loc(11, 14))]),
+                        Variant::new("B", vec![]),
+                    ],
+                    Loc(0, 18)
+                ),
+                Ast::match_(
+                    Ast::var("s".to_string(), Loc(25, 26)),
+                    vec![
+                        Arm::new(
+                            Pattern::ctor("A", vec![Pattern::var("n", Loc(31, 32))], Loc(29, 32)),
+                            Ast::var("n".to_string(), Loc(36, 37))
+                        ),
+                        Arm::new(
+                            Pattern::ctor("B", vec![], Loc(40, 41)),
+                            Ast::num(0, Loc(45, 46))
+                        ),
+                    ],
+                    Loc(19, 46)
+                ),
+            ],
+            Loc(0, 46)
+        ))
+    );
+
+    // "type U = A" is an alias, and "type U = | A" a type with the one
+    // constructor `A`
+    let ast = parser(vec![
+        Token::type_(Loc(0, 4)),
+        Token::upper("U", Loc(5, 6)),
+        Token::equal(Loc(7, 8)),
+        Token::upper("A", Loc(9, 10)),
+    ]);
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::type_alias(
+                "U".to_string(),
+                Type::name("A", Loc(9, 10)),
+                Loc(0, 10)
+            )],
+            Loc(0, 10)
+        ))
+    );
+    let ast = parser(vec![
+        Token::type_(Loc(0, 4)),
+        Token::upper("U", Loc(5, 6)),
+        Token::equal(Loc(7, 8)),
+        Token::bar(Loc(9, 10)),
+        Token::upper("A", Loc(11, 12)),
+    ]);
+    assert_eq!(
+        ast,
+        Ok(Ast::program(
+            vec![Ast::data(
+                "U".to_string(),
+                vec![Variant::new("A", vec![])],
+                Loc(0, 12)
+            )],
+            Loc(0, 12)
+        ))
+    );
+}
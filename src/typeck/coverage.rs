@@ -0,0 +1,367 @@
+use crate::parser::{Arm, Pattern, PatternKind};
+use crate::typeck::Ty;
+use crate::util::Integer;
+use std::collections::HashMap;
+use std::fmt;
+
+// a pattern reduced to the constructors it tests; a list pattern `[p, q]`
+// becomes `p :: q :: []`
+#[derive(Debug, Clone)]
+enum Pat {
+    Any,
+    Ctor(Ctor, Vec<Pat>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Ctor {
+    Bool(bool),
+    Num(Integer),
+    Str(String),
+    Nil,
+    Cons,
+    Data(String),
+    // the only constructor of tuples of this size
+    Tuple(usize),
+    // the only constructor of records with these fields
+    Record(Vec<String>),
+}
+
+impl Pat {
+    // whether the pattern needs parentheses as an argument or a list head
+    fn is_compound(&self) -> bool {
+        match self {
+            Pat::Ctor(Ctor::Cons, _) => true,
+            Pat::Ctor(Ctor::Tuple(_), _) | Pat::Ctor(Ctor::Record(_), _) => false,
+            Pat::Ctor(_, args) => !args.is_empty(),
+            Pat::Any => false,
+        }
+    }
+
+    // whether the pattern tells apart some values of its type
+    fn is_tested(&self) -> bool {
+        match self {
+            Pat::Ctor(Ctor::Record(_), args) => args.iter().any(Pat::is_tested),
+            Pat::Ctor(..) => true,
+            Pat::Any => false,
+        }
+    }
+}
+
+impl fmt::Display for Pat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (ctor, args) = match self {
+            Pat::Any => return write!(f, "_"),
+            Pat::Ctor(ctor, args) => (ctor, args),
+        };
+        match ctor {
+            Ctor::Bool(true) => write!(f, "True"),
+            Ctor::Bool(false) => write!(f, "False"),
+            Ctor::Num(n) => write!(f, "{}", n),
+            Ctor::Str(s) => write!(f, "{:?}", s),
+            Ctor::Nil => write!(f, "[]"),
+            Ctor::Cons if args[0].is_compound() => write!(f, "({}) :: {}", args[0], args[1]),
+            Ctor::Cons => write!(f, "{} :: {}", args[0], args[1]),
+            Ctor::Data(name) => {
+                write!(f, "{}", name)?;
+                for arg in args {
+                    if arg.is_compound() {
+                        write!(f, " ({})", arg)?;
+                    } else {
+                        write!(f, " {}", arg)?;
+                    }
+                }
+                Ok(())
+            }
+            Ctor::Tuple(_) => {
+                write!(f, "(")?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ")")
+            }
+            // only the fields that are tested
+            Ctor::Record(names) => {
+                let tested: Vec<_> = names
+                    .iter()
+                    .zip(args)
+                    .filter(|(_, arg)| arg.is_tested())
+                    .collect();
+                if tested.is_empty() {
+                    return write!(f, "_");
+                }
+                write!(f, "{{ ")?;
+                for (i, (name, arg)) in tested.into_iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{} = {}", name, arg)?;
+                }
+                write!(f, " }}")
+            }
+        }
+    }
+}
+
+/// Finds the values the arms of a `match` miss and the arms no value
+/// reaches, knowing the constructors of each type.
+pub struct Coverage<'a> {
+    ctors: &'a HashMap<String, (Vec<Ty>, Ty)>,
+    variants: &'a HashMap<String, Vec<String>>,
+}
+
+/// What a `match` covers.
+#[derive(Debug, PartialEq)]
+pub struct Report {
+    // the indices of the arms that only match values earlier arms do
+    pub unreachable: Vec<usize>,
+    // a pattern matching values no arm does, if there are any
+    pub missing: Option<String>,
+}
+
+impl<'a> Coverage<'a> {
+    pub fn new(
+        ctors: &'a HashMap<String, (Vec<Ty>, Ty)>,
+        variants: &'a HashMap<String, Vec<String>>,
+    ) -> Self {
+        Coverage { ctors, variants }
+    }
+
+    /// Checks the patterns of the arms of a `match` on a value of type `ty`.
+    pub fn check(&self, arms: &[Arm], ty: &Ty) -> Report {
+        let tys = [ty.clone()];
+        let mut rows = Vec::new();
+        let mut unreachable = Vec::new();
+        for (i, arm) in arms.iter().enumerate() {
+            let row = vec![self.lower(&arm.pat, ty)];
+            if !self.useful(&rows, &row, &tys) {
+                unreachable.push(i);
+            }
+            // a guard may fail, leaving the values to later arms
+            if arm.guard.is_none() {
+                rows.push(row);
+            }
+        }
+        let missing = self
+            .witness(&rows, &tys)
+            .map(|mut pats| pats.remove(0).to_string());
+        Report {
+            unreachable,
+            missing,
+        }
+    }
+
+    // `pat`, which matches values of type `ty`, reduced to its constructors.
+    // A record pattern gets a `_` for each field it leaves out.
+    fn lower(&self, pat: &Pattern, ty: &Ty) -> Pat {
+        let lower_all = |pats: &[Pattern], tys: Vec<Ty>| {
+            pats.iter()
+                .zip(&tys)
+                .map(|(pat, ty)| self.lower(pat, ty))
+                .collect()
+        };
+        match pat.value {
+            PatternKind::Wildcard | PatternKind::Var(_) => Pat::Any,
+            PatternKind::Num(ref n) => Pat::Ctor(Ctor::Num(n.clone()), Vec::new()),
+            PatternKind::Str(ref s) => Pat::Ctor(Ctor::Str(s.clone()), Vec::new()),
+            PatternKind::Bool(b) => Pat::Ctor(Ctor::Bool(b), Vec::new()),
+            PatternKind::List(ref pats) => {
+                let elem = self.arg_tys(&Ctor::Cons, ty, &[]).remove(0);
+                pats.iter()
+                    .rev()
+                    .fold(Pat::Ctor(Ctor::Nil, Vec::new()), |tail, head| {
+                        Pat::Ctor(Ctor::Cons, vec![self.lower(head, &elem), tail])
+                    })
+            }
+            PatternKind::Cons(ref head, ref tail) => {
+                let elem = self.arg_tys(&Ctor::Cons, ty, &[]).remove(0);
+                Pat::Ctor(
+                    Ctor::Cons,
+                    vec![self.lower(head, &elem), self.lower(tail, ty)],
+                )
+            }
+            PatternKind::Tuple(ref pats) => {
+                let ctor = Ctor::Tuple(pats.len());
+                let tys = self.arg_tys(&ctor, ty, &[]);
+                Pat::Ctor(ctor, lower_all(pats, tys))
+            }
+            PatternKind::Ctor(ref name, ref args) => {
+                let ctor = Ctor::Data(name.clone());
+                let tys = self.arg_tys(&ctor, ty, &[]);
+                Pat::Ctor(ctor, lower_all(args, tys))
+            }
+            PatternKind::Record(ref pats) => {
+                let fields = match ty {
+                    Ty::Record(fields) => fields,
+                    _ => return Pat::Any,
+                };
+                let args = fields
+                    .iter()
+                    .map(
+                        |(name, ty)| match pats.iter().find(|(field, _)| field == name) {
+                            Some((_, pat)) => self.lower(pat, ty),
+                            None => Pat::Any,
+                        },
+                    )
+                    .collect();
+                let names = fields.iter().map(|(name, _)| name.clone()).collect();
+                Pat::Ctor(Ctor::Record(names), args)
+            }
+        }
+    }
+
+    // whether some value matched by `row` is matched by none of `rows`
+    fn useful(&self, rows: &[Vec<Pat>], row: &[Pat], tys: &[Ty]) -> bool {
+        let (head, rest) = match row.split_first() {
+            Some(split) => split,
+            None => return rows.is_empty(),
+        };
+        let ty = &tys[0];
+        match head {
+            Pat::Ctor(ctor, args) => {
+                let row: Vec<_> = args.iter().chain(rest).cloned().collect();
+                let tys = self.arg_tys(ctor, ty, &tys[1..]);
+                self.useful(&specialize(rows, ctor, args.len()), &row, &tys)
+            }
+            Pat::Any => match self.complete(rows, ty) {
+                Some(ctors) => ctors.iter().any(|ctor| {
+                    let arity = self.arity(ctor, ty);
+                    let tys = self.arg_tys(ctor, ty, &tys[1..]);
+                    let row: Vec<_> = vec![Pat::Any; arity]
+                        .into_iter()
+                        .chain(rest.iter().cloned())
+                        .collect();
+                    self.useful(&specialize(rows, ctor, arity), &row, &tys)
+                }),
+                None => self.useful(&default(rows), rest, &tys[1..]),
+            },
+        }
+    }
+
+    // a row of patterns matching values of types `tys` that none of `rows`
+    // matches, if there is one
+    fn witness(&self, rows: &[Vec<Pat>], tys: &[Ty]) -> Option<Vec<Pat>> {
+        let ty = match tys.first() {
+            Some(ty) => ty,
+            None if rows.is_empty() => return Some(Vec::new()),
+            None => return None,
+        };
+        if let Some(ctors) = self.complete(rows, ty) {
+            return ctors.into_iter().find_map(|ctor| {
+                let arity = self.arity(&ctor, ty);
+                let tys = self.arg_tys(&ctor, ty, &tys[1..]);
+                let mut args = self.witness(&specialize(rows, &ctor, arity), &tys)?;
+                let rest = args.split_off(arity);
+                let mut row = vec![Pat::Ctor(ctor, args)];
+                row.extend(rest);
+                Some(row)
+            });
+        }
+        let mut rest = self.witness(&default(rows), &tys[1..])?;
+        // name a constructor none of the rows starts with, unless the rows
+        // test none or there are too many to name
+        let heads = heads(rows);
+        let head = match self.all_ctors(ty) {
+            Some(ctors) if !heads.is_empty() => {
+                let ctor = ctors.into_iter().find(|c| !heads.contains(c)).unwrap();
+                let arity = self.arity(&ctor, ty);
+                Pat::Ctor(ctor, vec![Pat::Any; arity])
+            }
+            _ => Pat::Any,
+        };
+        rest.insert(0, head);
+        Some(rest)
+    }
+
+    // every constructor of `ty` if the rows start with all of them
+    fn complete(&self, rows: &[Vec<Pat>], ty: &Ty) -> Option<Vec<Ctor>> {
+        let heads = heads(rows);
+        self.all_ctors(ty)
+            .filter(|ctors| ctors.iter().all(|ctor| heads.contains(ctor)))
+    }
+
+    // the constructors of `ty`, unless it has too many to list
+    fn all_ctors(&self, ty: &Ty) -> Option<Vec<Ctor>> {
+        match ty {
+            Ty::Bool => Some(vec![Ctor::Bool(true), Ctor::Bool(false)]),
+            Ty::List(_) => Some(vec![Ctor::Nil, Ctor::Cons]),
+            Ty::Tuple(elems) => Some(vec![Ctor::Tuple(elems.len())]),
+            Ty::Record(fields) => Some(vec![Ctor::Record(
+                fields.iter().map(|(name, _)| name.clone()).collect(),
+            )]),
+            Ty::Data(name) => self
+                .variants
+                .get(name)
+                .map(|variants| variants.iter().cloned().map(Ctor::Data).collect()),
+            _ => None,
+        }
+    }
+
+    fn arity(&self, ctor: &Ctor, ty: &Ty) -> usize {
+        self.arg_tys(ctor, ty, &[]).len()
+    }
+
+    // the types of the fields of `ctor`, a constructor of `ty`, followed
+    // by `rest`
+    fn arg_tys(&self, ctor: &Ctor, ty: &Ty, rest: &[Ty]) -> Vec<Ty> {
+        let mut tys = match ctor {
+            Ctor::Cons => match ty {
+                Ty::List(elem) => vec![(**elem).clone(), ty.clone()],
+                _ => vec![ty.clone(), ty.clone()],
+            },
+            Ctor::Data(name) => self
+                .ctors
+                .get(name)
+                .map_or_else(Vec::new, |(fields, _)| fields.clone()),
+            Ctor::Tuple(n) => match ty {
+                Ty::Tuple(elems) => elems.clone(),
+                _ => vec![ty.clone(); *n],
+            },
+            Ctor::Record(_) => match ty {
+                Ty::Record(fields) => fields.iter().map(|(_, ty)| ty.clone()).collect(),
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        };
+        tys.extend(rest.iter().cloned());
+        tys
+    }
+}
+
+// the constructors the rows start with
+fn heads(rows: &[Vec<Pat>]) -> Vec<Ctor> {
+    let mut heads = Vec::new();
+    for row in rows {
+        if let Pat::Ctor(ctor, _) = &row[0] {
+            if !heads.contains(ctor) {
+                heads.push(ctor.clone());
+            }
+        }
+    }
+    heads
+}
+
+// the rows matching a value built by `ctor`, with its fields in place of
+// the value
+fn specialize(rows: &[Vec<Pat>], ctor: &Ctor, arity: usize) -> Vec<Vec<Pat>> {
+    rows.iter()
+        .filter_map(|row| {
+            let args = match &row[0] {
+                Pat::Ctor(c, args) if c == ctor => args.clone(),
+                Pat::Ctor(..) => return None,
+                Pat::Any => vec![Pat::Any; arity],
+            };
+            Some(args.into_iter().chain(row[1..].iter().cloned()).collect())
+        })
+        .collect()
+}
+
+// the rows matching any value, without it
+fn default(rows: &[Vec<Pat>]) -> Vec<Vec<Pat>> {
+    rows.iter()
+        .filter(|row| matches!(row[0], Pat::Any))
+        .map(|row| row[1..].to_vec())
+        .collect()
+}
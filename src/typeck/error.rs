@@ -0,0 +1,122 @@
+use crate::error::{print_annot, SourceMap};
+use crate::lexer::Loc;
+use crate::typeck::{var_names, Ty};
+use crate::util::Annot;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeErrorKind {
+    Mismatch {
+        expected: Ty,
+        found: Ty,
+        // where the expected type comes from, if from an expression
+        expected_at: Option<Loc>,
+    },
+    // a type that would have to contain itself, as in `x => x x`
+    InfiniteType(Ty, Ty),
+    UnknownType(String),
+    // a type or constructor name that is already defined
+    DuplicateType(String),
+    DuplicateConstructor(String),
+    UnboundVariable(String),
+    // a variable a pattern binds more than once, as in `(x, x)`
+    DuplicateVariable(String),
+    NotAFunction(Ty),
+    NotANumber(Ty),
+    NotAList(Ty),
+    NotComparable(Ty),
+    NotConcatenable(Ty),
+    UnknownConstructor(String),
+    // a field taken from a value whose type has no such field
+    NoField {
+        field: String,
+        ty: Ty,
+    },
+    // a field taken from a value whose type is not known yet, which could
+    // be any record
+    UnknownRecord(String),
+    DuplicateField(String),
+    WrongArity {
+        ctor: String,
+        expected: usize,
+        found: usize,
+    },
+}
+
+pub type TypeError = Annot<TypeErrorKind>;
+
+use std::fmt;
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::TypeErrorKind::*;
+        match self.value {
+            Mismatch {
+                ref expected,
+                ref found,
+                ..
+            } => {
+                // a variable in both types has the same name in each
+                let names = var_names(vec![expected, found]);
+                write!(
+                    f,
+                    "type mismatch: expected {}, found {}",
+                    expected.named(&names),
+                    found.named(&names)
+                )
+            }
+            InfiniteType(ref var, ref ty) => {
+                let names = var_names(vec![var, ty]);
+                write!(
+                    f,
+                    "cannot construct the infinite type {} = {}",
+                    var.named(&names),
+                    ty.named(&names)
+                )
+            }
+            UnknownType(ref name) => write!(f, "unknown type {}", name),
+            DuplicateType(ref name) => write!(f, "type {} is already defined", name),
+            DuplicateConstructor(ref ctor) => write!(f, "constructor {} is already defined", ctor),
+            UnboundVariable(ref v) => write!(f, "variable {} is not bound", v),
+            DuplicateVariable(ref v) => write!(f, "variable {} is bound twice in the pattern", v),
+            NotAFunction(ref ty) => write!(f, "{} is not a function", ty),
+            NotANumber(ref ty) => write!(f, "{} is not a number", ty),
+            NotAList(ref ty) => write!(f, "{} is not a list", ty),
+            NotComparable(ref ty) => write!(f, "values of type {} cannot be compared", ty),
+            NotConcatenable(ref ty) => write!(f, "values of type {} cannot be joined with ++", ty),
+            UnknownConstructor(ref ctor) => write!(f, "unknown constructor {}", ctor),
+            NoField { ref field, ref ty } => write!(f, "type {} has no field {}", ty, field),
+            UnknownRecord(ref field) => write!(
+                f,
+                "the type of the record must be known to use its field {}; add a signature",
+                field
+            ),
+            DuplicateField(ref field) => write!(f, "field {} is given more than once", field),
+            WrongArity {
+                ref ctor,
+                expected,
+                found,
+            } => write!(
+                f,
+                "constructor {} takes {} arguments, found {}",
+                ctor, expected, found
+            ),
+        }
+    }
+}
+
+impl TypeError {
+    pub fn show_diagnostic(&self, src: &SourceMap) {
+        eprintln!("{}", self);
+        print_annot(src, self.loc.clone());
+        if let TypeErrorKind::Mismatch {
+            expected_at: Some(ref loc),
+            ..
+        } = self.value
+        {
+            eprintln!("the expected type comes from here");
+            print_annot(src, loc.clone());
+        }
+    }
+}
+
+use std::error::Error as StdError;
+impl StdError for TypeError {}
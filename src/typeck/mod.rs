@@ -0,0 +1,14 @@
+mod coverage;
+mod error;
+mod ty;
+#[allow(clippy::module_inception)]
+mod typeck;
+mod unify;
+mod warning;
+
+pub use coverage::*;
+pub use error::*;
+pub use ty::*;
+pub use typeck::*;
+pub use unify::*;
+pub use warning::*;
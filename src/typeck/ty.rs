@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ty {
+    Int,
+    Float,
+    String,
+    Bool,
+    Fun(Box<Ty>, Box<Ty>),
+    List(Box<Ty>),
+    // `(A, B)`, with at least two elements
+    Tuple(Vec<Ty>),
+    // `{ a : A, b : B }`, with the fields sorted by name; two are the same
+    // only when they have the same fields
+    Record(Vec<(String, Ty)>),
+    // a type defined with `type Name = A | B`; two are the same only when
+    // their names are
+    Data(String),
+    // statements such as bindings, which evaluate to `Value::Void`
+    Void,
+    // a type not known yet, to be found by unification
+    Var(usize),
+    // a type variable of a signature while the binding is checked against
+    // it; it stands for any type, so it is only the same as itself
+    Param(String),
+}
+
+impl Ty {
+    pub fn fun(arg: Ty, ret: Ty) -> Self {
+        Ty::Fun(Box::new(arg), Box::new(ret))
+    }
+
+    pub fn list(elem: Ty) -> Self {
+        Ty::List(Box::new(elem))
+    }
+
+    pub fn record(mut fields: Vec<(String, Ty)>) -> Self {
+        fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ty::Record(fields)
+    }
+
+    /// The type of the field `name` of this record type.
+    pub fn field(&self, name: &str) -> Option<&Ty> {
+        match self {
+            Ty::Record(fields) => fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, ty)| ty),
+            _ => None,
+        }
+    }
+
+    /// The type variables in this type, in order of first appearance.
+    pub fn vars(&self) -> Vec<usize> {
+        let mut vars = Vec::new();
+        self.visit(&mut |ty| match ty {
+            Ty::Var(v) if !vars.contains(v) => vars.push(*v),
+            _ => (),
+        });
+        vars
+    }
+
+    // calls `f` on this type and every type in it
+    fn visit(&self, f: &mut impl FnMut(&Ty)) {
+        f(self);
+        match self {
+            Ty::Fun(arg, ret) => {
+                arg.visit(f);
+                ret.visit(f);
+            }
+            Ty::List(elem) => elem.visit(f),
+            Ty::Tuple(elems) => elems.iter().for_each(|elem| elem.visit(f)),
+            Ty::Record(fields) => fields.iter().for_each(|(_, ty)| ty.visit(f)),
+            _ => (),
+        }
+    }
+
+    /// Displays the type with the variables in `names` called by their
+    /// entry.
+    pub fn named<'a>(&'a self, names: &'a HashMap<usize, String>) -> Named<'a> {
+        Named(self, names)
+    }
+
+    fn fmt_named(&self, names: &HashMap<usize, String>, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Ty::*;
+        match self {
+            Int => write!(f, "Int"),
+            Float => write!(f, "Float"),
+            String => write!(f, "String"),
+            Bool => write!(f, "Bool"),
+            Fun(arg, ret) => {
+                if let Fun(..) = **arg {
+                    write!(f, "(")?;
+                    arg.fmt_named(names, f)?;
+                    write!(f, ")")?;
+                } else {
+                    arg.fmt_named(names, f)?;
+                }
+                write!(f, " -> ")?;
+                ret.fmt_named(names, f)
+            }
+            List(elem) => {
+                write!(f, "[")?;
+                elem.fmt_named(names, f)?;
+                write!(f, "]")
+            }
+            Tuple(elems) => {
+                write!(f, "(")?;
+                for (i, elem) in elems.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    elem.fmt_named(names, f)?;
+                }
+                write!(f, ")")
+            }
+            Record(fields) if fields.is_empty() => write!(f, "{{}}"),
+            Record(fields) => {
+                write!(f, "{{ ")?;
+                for (i, (name, ty)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{} : ", name)?;
+                    ty.fmt_named(names, f)?;
+                }
+                write!(f, " }}")
+            }
+            Data(name) => write!(f, "{}", name),
+            Void => write!(f, "()"),
+            Var(v) => match names.get(v) {
+                Some(name) => write!(f, "{}", name),
+                None => write!(f, "t{}", v),
+            },
+            Param(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Names for the type variables in `tys`: a, b, ... in order of first
+/// appearance, skipping the names of signature variables in them.
+pub fn var_names<'a>(tys: impl IntoIterator<Item = &'a Ty>) -> HashMap<usize, String> {
+    let mut vars = Vec::new();
+    let mut taken = Vec::new();
+    for ty in tys {
+        ty.visit(&mut |ty| match ty {
+            Ty::Var(v) if !vars.contains(v) => vars.push(*v),
+            Ty::Param(name) => taken.push(name.clone()),
+            _ => (),
+        });
+    }
+    let mut names = (0..).map(var_name).filter(|name| !taken.contains(name));
+    vars.into_iter()
+        .map(|v| (v, names.next().unwrap()))
+        .collect()
+}
+
+pub struct Named<'a>(&'a Ty, &'a HashMap<usize, String>);
+
+impl<'a> fmt::Display for Named<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt_named(self.1, f)
+    }
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_named(&var_names(Some(self)), f)
+    }
+}
+
+/// A set of types sharing some operators, which a type variable can be
+/// restricted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Class {
+    // compared with `==` and `!=`
+    Eq,
+    // compared with `<` and the like
+    Ord,
+    // used in arithmetic
+    Num,
+    // joined with `++`
+    Concat,
+}
+
+impl Class {
+    /// Whether every member of `other` is also a member of this class.
+    pub fn implies(self, other: Class) -> bool {
+        use self::Class::*;
+        match (self, other) {
+            (Num, Ord) | (Num, Eq) | (Ord, Eq) => true,
+            (a, b) => a == b,
+        }
+    }
+}
+
+impl fmt::Display for Class {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// A type whose variables in `vars` stand for any type in their classes,
+/// e.g. the type of `x => x` is `a -> a` for every `a`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scheme {
+    pub vars: Vec<(usize, Vec<Class>)>,
+    pub ty: Ty,
+}
+
+impl Scheme {
+    /// A type with nothing to instantiate.
+    pub fn mono(ty: Ty) -> Self {
+        Scheme {
+            vars: Vec::new(),
+            ty,
+        }
+    }
+}
+
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = var_names(Some(&self.ty));
+        let mut constraints = Vec::new();
+        for (var, classes) in &self.vars {
+            let name = match names.get(var) {
+                Some(name) => name,
+                None => continue,
+            };
+            // `Num a` already says that `a` is ordered
+            for &class in classes {
+                if !classes.iter().any(|&c| c != class && c.implies(class)) {
+                    constraints.push(format!("{} {}", class, name));
+                }
+            }
+        }
+        constraints.sort();
+        match constraints.len() {
+            0 => (),
+            1 => write!(f, "{} => ", constraints[0])?,
+            _ => write!(f, "({}) => ", constraints.join(", "))?,
+        }
+        self.ty.fmt_named(&names, f)
+    }
+}
+
+// a, b, ..., z, a1, b1, ...
+fn var_name(i: usize) -> String {
+    let letter = (b'a' + (i % 26) as u8) as char;
+    match i / 26 {
+        0 => letter.to_string(),
+        n => format!("{}{}", letter, n),
+    }
+}
+
+#[test]
+fn test_display_ty() {
+    let ty = Ty::fun(Ty::fun(Ty::Int, Ty::Int), Ty::fun(Ty::list(Ty::Int), Ty::String));
+    assert_eq!(ty.to_string(), "(Int -> Int) -> [Int] -> String");
+    let ty = Ty::fun(Ty::Tuple(vec![Ty::Int, Ty::list(Ty::Bool)]), Ty::Int);
+    assert_eq!(ty.to_string(), "(Int, [Bool]) -> Int");
+    let ty = Ty::record(vec![
+        ("name".to_string(), Ty::String),
+        ("age".to_string(), Ty::Int),
+    ]);
+    assert_eq!(ty.to_string(), "{ age : Int, name : String }");
+
+    // variables are named by their order, around those of signatures
+    let ty = Ty::fun(
+        Ty::Var(5),
+        Ty::fun(Ty::Param("a".to_string()), Ty::list(Ty::Var(2))),
+    );
+    assert_eq!(ty.to_string(), "b -> a -> [c]");
+}
+
+#[test]
+fn test_display_scheme() {
+    let ty = Ty::fun(Ty::Var(7), Ty::fun(Ty::list(Ty::Var(3)), Ty::Var(7)));
+    let scheme = Scheme {
+        vars: vec![(3, vec![]), (7, vec![Class::Eq, Class::Num])],
+        ty: ty.clone(),
+    };
+    assert_eq!(scheme.to_string(), "Num a => a -> [b] -> a");
+    let scheme = Scheme {
+        vars: vec![(3, vec![Class::Eq]), (7, vec![Class::Ord])],
+        ty,
+    };
+    assert_eq!(scheme.to_string(), "(Eq b, Ord a) => a -> [b] -> a");
+    assert_eq!(Scheme::mono(Ty::Var(2)).to_string(), "a");
+}
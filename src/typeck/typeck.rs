@@ -0,0 +1,1513 @@
+use crate::lexer::Loc;
+use crate::parser::{Arm, Ast, BinOpKind, Pattern, Type, UniOpKind};
+use crate::typeck::{
+    Class, Coverage, Scheme, Ty, TypeError, TypeErrorKind, Unifier, UnifyError, Warning,
+    WarningKind,
+};
+use std::collections::{HashMap, HashSet};
+
+pub struct TypeChecker {
+    // types of the bound variables
+    env: HashMap<String, Scheme>,
+    // signatures declared with `::=` and where their types are written,
+    // which later bindings must conform to
+    decls: HashMap<String, (Ty, Option<Loc>)>,
+    // what each type name stands for; an alias maps to the aliased type
+    types: HashMap<String, Ty>,
+    // the field types and the type of the values built by each constructor
+    ctors: HashMap<String, (Vec<Ty>, Ty)>,
+    // the constructors of each sum type, in the order they are defined
+    variants: HashMap<String, Vec<String>>,
+    unifier: Unifier,
+    // found while checking and not yet taken
+    warnings: Vec<Warning>,
+}
+
+impl TypeChecker {
+    pub fn new() -> Self {
+        let mut env = HashMap::new();
+        env.insert(
+            "String".to_string(),
+            Scheme::mono(Ty::fun(Ty::Int, Ty::String)),
+        );
+        let types = [
+            ("Int", Ty::Int),
+            ("Float", Ty::Float),
+            ("String", Ty::String),
+            ("Bool", Ty::Bool),
+        ]
+        .iter()
+        .map(|(name, ty)| (name.to_string(), ty.clone()))
+        .collect();
+        TypeChecker {
+            env,
+            decls: HashMap::new(),
+            types,
+            ctors: HashMap::new(),
+            variants: HashMap::new(),
+            unifier: Unifier::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Infers the type of `expr`, keeping its bindings for later checks.
+    pub fn check(&mut self, expr: &Ast) -> Result<Ty, TypeError> {
+        let ty = self.infer(expr)?;
+        Ok(self.unifier.resolve(&ty))
+    }
+
+    /// Forgets where earlier declarations were written, before checking a
+    /// source their locations do not refer to, e.g. the next REPL line.
+    pub fn new_source(&mut self) {
+        for (_, loc) in self.decls.values_mut() {
+            *loc = None;
+        }
+    }
+
+    /// The warnings found by the checks so far, which are not returned again.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// The inferred type of a variable bound by an earlier check.
+    pub fn scheme_of(&self, var: &str) -> Option<&Scheme> {
+        self.env.get(var)
+    }
+
+    fn infer(&mut self, expr: &Ast) -> Result<Ty, TypeError> {
+        use crate::parser::AstKind::*;
+        match expr.value {
+            Num(_) => Ok(Ty::Int),
+            Float(_) => Ok(Ty::Float),
+            Str(_) => Ok(Ty::String),
+            Bool(_) => Ok(Ty::Bool),
+            List(ref elems) => {
+                // every element must have the type of the first one
+                let elem = self.unifier.fresh();
+                for e in elems {
+                    let ty = self.infer(e)?;
+                    self.unify(&elem, &ty, &e.loc, Some(&elems[0].loc))?;
+                }
+                Ok(Ty::list(elem))
+            }
+            Tuple(ref elems) => Ok(Ty::Tuple(
+                elems
+                    .iter()
+                    .map(|e| self.infer(e))
+                    .collect::<Result<_, _>>()?,
+            )),
+            Record(ref fields) => {
+                unique_fields(fields, &expr.loc)?;
+                let fields = fields
+                    .iter()
+                    .map(|(name, e)| Ok((name.clone(), self.infer(e)?)))
+                    .collect::<Result<_, _>>()?;
+                Ok(Ty::record(fields))
+            }
+            Update {
+                ref record,
+                ref fields,
+            } => {
+                unique_fields(fields, &expr.loc)?;
+                // the new values must have the types of the old ones
+                let ty = self.infer(record)?;
+                for (name, e) in fields {
+                    let field = self.field(&ty, name, &expr.loc)?;
+                    self.expect(e, &field, Some(&record.loc))?;
+                }
+                Ok(ty)
+            }
+            Field {
+                ref record,
+                ref field,
+            } => {
+                let ty = self.infer(record)?;
+                self.field(&ty, field, &expr.loc)
+            }
+            UniOp { ref op, ref e } if op.value == UniOpKind::Not => {
+                self.expect(e, &Ty::Bool, None)?;
+                Ok(Ty::Bool)
+            }
+            UniOp { ref e, .. } => {
+                let ty = self.infer(e)?;
+                self.constrain(&ty, Class::Num, &e.loc)?;
+                Ok(ty)
+            }
+            BinOp {
+                ref op,
+                ref lhs,
+                ref rhs,
+            } if op.value == BinOpKind::And || op.value == BinOpKind::Or => {
+                self.expect(lhs, &Ty::Bool, None)?;
+                self.expect(rhs, &Ty::Bool, None)?;
+                Ok(Ty::Bool)
+            }
+            BinOp {
+                ref op,
+                ref lhs,
+                ref rhs,
+            } if op.value.is_comparison() => {
+                let l = self.infer(lhs)?;
+                let r = self.infer(rhs)?;
+                // an Int may be compared with a Float
+                if !self.mixed_numbers(&l, &r) {
+                    self.unify(&l, &r, &rhs.loc, Some(&lhs.loc))?;
+                }
+                // only numbers and strings have an order
+                let class = match op.value {
+                    BinOpKind::Eq | BinOpKind::Ne => Class::Eq,
+                    _ => Class::Ord,
+                };
+                self.constrain(&l, class, &expr.loc)?;
+                Ok(Ty::Bool)
+            }
+            BinOp {
+                ref op,
+                ref lhs,
+                ref rhs,
+            } if op.value == BinOpKind::Concat => {
+                // `++` joins two strings or two lists
+                let l = self.infer(lhs)?;
+                self.constrain(&l, Class::Concat, &lhs.loc)?;
+                let r = self.infer(rhs)?;
+                self.unify(&l, &r, &rhs.loc, Some(&lhs.loc))?;
+                Ok(l)
+            }
+            BinOp {
+                ref op,
+                ref lhs,
+                ref rhs,
+            } if op.value == BinOpKind::Cons => {
+                let list = Ty::list(self.infer(lhs)?);
+                let tail = self.infer(rhs)?;
+                self.unify(&list, &tail, &rhs.loc, Some(&lhs.loc))?;
+                Ok(list)
+            }
+            BinOp {
+                ref lhs, ref rhs, ..
+            } => {
+                let l = self.infer(lhs)?;
+                self.constrain(&l, Class::Num, &lhs.loc)?;
+                let r = self.infer(rhs)?;
+                self.constrain(&r, Class::Num, &rhs.loc)?;
+                // an Int mixed with a Float is promoted to Float
+                if self.mixed_numbers(&l, &r) {
+                    return Ok(Ty::Float);
+                }
+                self.unify(&l, &r, &rhs.loc, Some(&lhs.loc))?;
+                Ok(l)
+            }
+            Bind {
+                ref var, ref body, ..
+            } => {
+                let decl = self.decls.get(var).cloned();
+                // a function may call itself, so its own name is bound in
+                // its body
+                let own = match decl {
+                    Some((ref ty, _)) => ty.clone(),
+                    None => self.unifier.fresh(),
+                };
+                let rec = match body.value {
+                    Lambda { .. } => vec![(var.clone(), own.clone())],
+                    _ => Vec::new(),
+                };
+                let ty = self.with_bindings(rec, |checker| match decl {
+                    Some((ref decl, ref at)) => checker
+                        .expect(body, decl, at.as_ref())
+                        .map(|()| decl.clone()),
+                    None => {
+                        let ty = checker.infer(body)?;
+                        checker.unify(&own, &ty, &body.loc, None)?;
+                        Ok(ty)
+                    }
+                })?;
+                // the variables of a signature are instantiated anew at each
+                // use, but not while checking the body
+                let scheme = match decl {
+                    Some(_) => self.unifier.parametric(&ty),
+                    None => self.unifier.generalize(&ty, self.env.values()),
+                };
+                self.env.insert(var.clone(), scheme);
+                Ok(Ty::Void)
+            }
+            Destructure { ref pat, ref body } => {
+                let ty = self.infer(body)?;
+                let mut bindings = Vec::new();
+                self.check_pattern(pat, &ty, &body.loc, &mut bindings)?;
+                for (var, ty) in bindings {
+                    let scheme = match self.decls.get(&var).cloned() {
+                        Some((decl, at)) => {
+                            self.unify(&decl, &ty, &pat.loc, at.as_ref())?;
+                            self.unifier.parametric(&decl)
+                        }
+                        None => self.unifier.generalize(&ty, self.env.values()),
+                    };
+                    self.env.insert(var, scheme);
+                }
+                Ok(Ty::Void)
+            }
+            TypeDecl {
+                ref var, ref ty, ..
+            } => {
+                let loc = ty.loc.clone();
+                let ty = self.resolve(ty, true)?;
+                if let Some(bound) = self.env.get(var).cloned() {
+                    let bound = self.unifier.instantiate(&bound);
+                    self.unify(&ty, &bound, &expr.loc, None)?;
+                }
+                self.decls.insert(var.clone(), (ty, Some(loc)));
+                Ok(Ty::Void)
+            }
+            TypeAlias {
+                ref name, ref ty, ..
+            } => {
+                self.new_type(name, &expr.loc)?;
+                let ty = self.resolve(ty, false)?;
+                self.types.insert(name.clone(), ty);
+                Ok(Ty::Void)
+            }
+            Data {
+                ref name,
+                ref variants,
+                ..
+            } => {
+                // values of a type made earlier must not match the
+                // constructors of a new one with the same name
+                self.new_type(name, &expr.loc)?;
+                let mut seen = HashSet::new();
+                for variant in variants {
+                    let name = &variant.name;
+                    if self.ctors.contains_key(name) || !seen.insert(name) {
+                        return Err(TypeError::new(
+                            TypeErrorKind::DuplicateConstructor(name.clone()),
+                            expr.loc.clone(),
+                        ));
+                    }
+                }
+                let data = Ty::Data(name.clone());
+                self.types.insert(name.clone(), data.clone());
+                // values are compared field by field, so one field that
+                // cannot be compared makes the whole type incomparable
+                let mut comparable = true;
+                for variant in variants {
+                    let fields = variant
+                        .fields
+                        .iter()
+                        .map(|field| self.resolve(field, false))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    comparable &= fields
+                        .iter()
+                        .all(|field| self.unifier.constrain(field, Class::Eq));
+                    // a constructor is a curried function of its fields
+                    let ty = fields
+                        .iter()
+                        .rev()
+                        .fold(data.clone(), |ret, field| Ty::fun(field.clone(), ret));
+                    self.env.insert(variant.name.clone(), Scheme::mono(ty));
+                    self.ctors
+                        .insert(variant.name.clone(), (fields, data.clone()));
+                }
+                if !comparable {
+                    self.unifier.incomparable(name);
+                }
+                let names = variants.iter().map(|v| v.name.clone()).collect();
+                self.variants.insert(name.clone(), names);
+                Ok(Ty::Void)
+            }
+            Var(ref s) => match self.env.get(s).cloned() {
+                Some(scheme) => Ok(self.unifier.instantiate(&scheme)),
+                None => Err(TypeError::new(
+                    TypeErrorKind::UnboundVariable(s.clone()),
+                    expr.loc.clone(),
+                )),
+            },
+            Lambda {
+                ref param,
+                ref body,
+            } => {
+                let arg = self.unifier.fresh();
+                let ret = self.with_bindings(vec![(param.clone(), arg.clone())], |checker| {
+                    checker.infer(body)
+                })?;
+                Ok(Ty::fun(arg, ret))
+            }
+            App { ref fun, ref arg } => {
+                let ty = self.infer(fun)?;
+                match self.unifier.resolve(&ty) {
+                    Ty::Fun(param, ret) => {
+                        self.expect(arg, &param, Some(&fun.loc))?;
+                        Ok(*ret)
+                    }
+                    Ty::Var(_) => {
+                        let param = self.infer(arg)?;
+                        let ret = self.unifier.fresh();
+                        self.unify(&ty, &Ty::fun(param, ret.clone()), &fun.loc, None)?;
+                        Ok(ret)
+                    }
+                    ty => Err(TypeError::new(
+                        TypeErrorKind::NotAFunction(ty),
+                        fun.loc.clone(),
+                    )),
+                }
+            }
+            Index {
+                ref list,
+                ref index,
+            } => {
+                let ty = self.infer(list)?;
+                let elem = self.unifier.fresh();
+                if self.unifier.unify(&Ty::list(elem.clone()), &ty).is_err() {
+                    return Err(TypeError::new(
+                        TypeErrorKind::NotAList(self.unifier.resolve(&ty)),
+                        list.loc.clone(),
+                    ));
+                }
+                self.expect(index, &Ty::Int, None)?;
+                Ok(elem)
+            }
+            Match {
+                ref scrutinee,
+                ref arms,
+            } => {
+                let scrutinee_ty = self.infer(scrutinee)?;
+                // every arm must have the type of the first one
+                let ty = self.unifier.fresh();
+                for arm in arms {
+                    let mut bindings = Vec::new();
+                    self.check_pattern(&arm.pat, &scrutinee_ty, &scrutinee.loc, &mut bindings)?;
+                    let found = self.with_bindings(bindings, |checker| {
+                        if let Some(ref guard) = arm.guard {
+                            checker.expect(guard, &Ty::Bool, None)?;
+                        }
+                        checker.infer(&arm.body)
+                    })?;
+                    self.unify(&ty, &found, &arm.body.loc, Some(&arms[0].body.loc))?;
+                }
+                // a missing case is reported at `match` and the scrutinee
+                let head = Loc(expr.loc.0, scrutinee.loc.1);
+                self.check_coverage(head, arms, &scrutinee_ty);
+                Ok(ty)
+            }
+            If {
+                ref cond,
+                ref then,
+                ref els,
+            } => {
+                self.expect(cond, &Ty::Bool, None)?;
+                let ty = self.infer(then)?;
+                let found = self.infer(els)?;
+                self.unify(&ty, &found, &els.loc, Some(&then.loc))?;
+                Ok(ty)
+            }
+            Block(ref stmts) => {
+                // bindings and declarations made in the block are dropped at its end
+                let outer = (self.env.clone(), self.decls.clone());
+                let mut last = Ok(Ty::Void);
+                for stmt in stmts {
+                    last = self.infer(stmt);
+                    if last.is_err() {
+                        break;
+                    }
+                }
+                self.env = outer.0;
+                self.decls = outer.1;
+                last
+            }
+            Program(ref stmts) => {
+                let mut last = Ty::Void;
+                for stmt in stmts {
+                    last = self.infer(stmt)?;
+                }
+                Ok(last)
+            }
+        }
+    }
+
+    // checks that `expr` has the `expected` type. A lambda checked against a
+    // function type takes its parameter type from there.
+    fn expect(
+        &mut self,
+        expr: &Ast,
+        expected: &Ty,
+        expected_at: Option<&Loc>,
+    ) -> Result<(), TypeError> {
+        use crate::parser::AstKind::Lambda;
+        if let (Lambda { param, body }, Ty::Fun(arg, ret)) =
+            (&expr.value, self.unifier.resolve(expected))
+        {
+            return self.with_bindings(vec![(param.clone(), *arg)], |checker| {
+                checker.expect(body, &ret, expected_at)
+            });
+        }
+        let found = self.infer(expr)?;
+        self.unify(expected, &found, &expr.loc, expected_at)
+    }
+
+    // unifies the type of the expression at `at` with the type it is
+    // expected to have
+    fn unify(
+        &mut self,
+        expected: &Ty,
+        found: &Ty,
+        at: &Loc,
+        expected_at: Option<&Loc>,
+    ) -> Result<(), TypeError> {
+        let kind = match self.unifier.unify(expected, found) {
+            Ok(()) => return Ok(()),
+            Err(UnifyError::Mismatch) => TypeErrorKind::Mismatch {
+                expected: self.unifier.resolve(expected),
+                found: self.unifier.resolve(found),
+                expected_at: expected_at.cloned(),
+            },
+            Err(UnifyError::Infinite(var, ty)) => TypeErrorKind::InfiniteType(Ty::Var(var), ty),
+            Err(UnifyError::Class(class, ty)) => class_error(class, ty),
+        };
+        Err(TypeError::new(kind, at.clone()))
+    }
+
+    // checks that the type of the expression at `at` can be in `class`
+    fn constrain(&mut self, ty: &Ty, class: Class, at: &Loc) -> Result<(), TypeError> {
+        if self.unifier.constrain(ty, class) {
+            Ok(())
+        } else {
+            let kind = class_error(class, self.unifier.resolve(ty));
+            Err(TypeError::new(kind, at.clone()))
+        }
+    }
+
+    // the type of the field `name` of a value of type `ty`, which must be a
+    // known record type
+    fn field(&self, ty: &Ty, name: &str, at: &Loc) -> Result<Ty, TypeError> {
+        let ty = self.unifier.resolve(ty);
+        let kind = match ty.field(name) {
+            Some(field) => return Ok(field.clone()),
+            None if matches!(ty, Ty::Var(_)) => TypeErrorKind::UnknownRecord(name.to_string()),
+            None => TypeErrorKind::NoField {
+                field: name.to_string(),
+                ty,
+            },
+        };
+        Err(TypeError::new(kind, at.clone()))
+    }
+
+    fn mixed_numbers(&self, l: &Ty, r: &Ty) -> bool {
+        matches!(
+            (self.unifier.resolve(l), self.unifier.resolve(r)),
+            (Ty::Int, Ty::Float) | (Ty::Float, Ty::Int)
+        )
+    }
+
+    // fails if a type called `name` is already defined
+    fn new_type(&self, name: &str, at: &Loc) -> Result<(), TypeError> {
+        if self.types.contains_key(name) {
+            return Err(TypeError::new(
+                TypeErrorKind::DuplicateType(name.to_string()),
+                at.clone(),
+            ));
+        }
+        Ok(())
+    }
+
+    // runs `f` with `bindings` added, restoring any outer bindings after
+    fn with_bindings<T>(
+        &mut self,
+        bindings: Vec<(String, Ty)>,
+        f: impl FnOnce(&mut Self) -> Result<T, TypeError>,
+    ) -> Result<T, TypeError> {
+        let outer: Vec<_> = bindings
+            .into_iter()
+            .map(|(var, ty)| {
+                let outer = self.env.insert(var.clone(), Scheme::mono(ty));
+                (var, outer)
+            })
+            .collect();
+        let result = f(self);
+        for (var, outer) in outer.into_iter().rev() {
+            match outer {
+                Some(outer) => self.env.insert(var, outer),
+                None => self.env.remove(&var),
+            };
+        }
+        result
+    }
+
+    // checks that `pat` can match a value of type `ty` from the scrutinee
+    // at `scrutinee`, collecting the variables it binds
+    fn check_pattern(
+        &mut self,
+        pat: &Pattern,
+        ty: &Ty,
+        scrutinee: &Loc,
+        bindings: &mut Vec<(String, Ty)>,
+    ) -> Result<(), TypeError> {
+        use crate::parser::PatternKind::*;
+        let found = match pat.value {
+            Wildcard => return Ok(()),
+            Var(ref var) => {
+                if bindings.iter().any(|(bound, _)| bound == var) {
+                    return Err(TypeError::new(
+                        TypeErrorKind::DuplicateVariable(var.clone()),
+                        pat.loc.clone(),
+                    ));
+                }
+                bindings.push((var.clone(), ty.clone()));
+                return Ok(());
+            }
+            Num(_) => Ty::Int,
+            Str(_) => Ty::String,
+            Bool(_) => Ty::Bool,
+            List(_) | Cons(..) => Ty::list(self.unifier.fresh()),
+            Tuple(ref pats) => Ty::Tuple(pats.iter().map(|_| self.unifier.fresh()).collect()),
+            // a pattern may leave out fields, so it does not tell the whole
+            // type
+            Record(ref fields) => {
+                unique_fields(fields, &pat.loc)?;
+                for (name, field) in fields {
+                    let ty = self.field(ty, name, &pat.loc)?;
+                    self.check_pattern(field, &ty, scrutinee, bindings)?;
+                }
+                return Ok(());
+            }
+            Ctor(ref ctor, _) => match self.ctors.get(ctor) {
+                Some((_, data)) => data.clone(),
+                None => {
+                    return Err(TypeError::new(
+                        TypeErrorKind::UnknownConstructor(ctor.clone()),
+                        pat.loc.clone(),
+                    ))
+                }
+            },
+        };
+        self.unify(ty, &found, &pat.loc, Some(scrutinee))?;
+        match (&pat.value, found) {
+            (List(pats), Ty::List(elem)) => {
+                for pat in pats {
+                    self.check_pattern(pat, &elem, scrutinee, bindings)?;
+                }
+            }
+            (Cons(head, tail), list @ Ty::List(_)) => {
+                if let Ty::List(ref elem) = list {
+                    self.check_pattern(head, elem, scrutinee, bindings)?;
+                }
+                self.check_pattern(tail, &list, scrutinee, bindings)?;
+            }
+            (Tuple(pats), Ty::Tuple(elems)) => {
+                for (pat, elem) in pats.iter().zip(&elems) {
+                    self.check_pattern(pat, elem, scrutinee, bindings)?;
+                }
+            }
+            (Ctor(ctor, args), _) => {
+                let fields = self.ctors[ctor].0.clone();
+                if fields.len() != args.len() {
+                    return Err(TypeError::new(
+                        TypeErrorKind::WrongArity {
+                            ctor: ctor.clone(),
+                            expected: fields.len(),
+                            found: args.len(),
+                        },
+                        pat.loc.clone(),
+                    ));
+                }
+                for (arg, field) in args.iter().zip(&fields) {
+                    self.check_pattern(arg, field, scrutinee, bindings)?;
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    // warns about the arms of the `match` at `head` that can never be
+    // reached and the values none of them match
+    fn check_coverage(&mut self, head: Loc, arms: &[Arm], scrutinee: &Ty) {
+        let ty = self.unifier.resolve(scrutinee);
+        let report = Coverage::new(&self.ctors, &self.variants).check(arms, &ty);
+        for i in report.unreachable {
+            let loc = arms[i].pat.loc.merge(&arms[i].body.loc);
+            self.warnings
+                .push(Warning::new(WarningKind::UnreachableArm, loc));
+        }
+        if let Some(pat) = report.missing {
+            self.warnings
+                .push(Warning::new(WarningKind::NonExhaustive(pat), head));
+        }
+    }
+
+    // turns the syntax of a type annotation into a `Ty`. Type variables
+    // are only allowed in signatures, where `params` is set.
+    fn resolve(&self, ty: &Type, params: bool) -> Result<Ty, TypeError> {
+        use crate::parser::TypeKind::*;
+        match ty.value {
+            Name(ref name) => self.types.get(name).cloned().ok_or_else(|| {
+                TypeError::new(TypeErrorKind::UnknownType(name.clone()), ty.loc.clone())
+            }),
+            Var(ref name) if params => Ok(Ty::Param(name.clone())),
+            Var(ref name) => Err(TypeError::new(
+                TypeErrorKind::UnknownType(name.clone()),
+                ty.loc.clone(),
+            )),
+            Fun(ref arg, ref ret) => Ok(Ty::fun(
+                self.resolve(arg, params)?,
+                self.resolve(ret, params)?,
+            )),
+            List(ref elem) => Ok(Ty::list(self.resolve(elem, params)?)),
+            Tuple(ref elems) => Ok(Ty::Tuple(
+                elems
+                    .iter()
+                    .map(|elem| self.resolve(elem, params))
+                    .collect::<Result<_, _>>()?,
+            )),
+            Record(ref fields) => {
+                unique_fields(fields, &ty.loc)?;
+                let fields = fields
+                    .iter()
+                    .map(|(name, field)| Ok((name.clone(), self.resolve(field, params)?)))
+                    .collect::<Result<_, _>>()?;
+                Ok(Ty::record(fields))
+            }
+        }
+    }
+}
+
+// checks that the record at `at` names each of its fields once
+fn unique_fields<T>(fields: &[(String, T)], at: &Loc) -> Result<(), TypeError> {
+    for (i, (name, _)) in fields.iter().enumerate() {
+        if fields[..i].iter().any(|(other, _)| other == name) {
+            return Err(TypeError::new(
+                TypeErrorKind::DuplicateField(name.clone()),
+                at.clone(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+// the error for a type outside of a class it must be in
+fn class_error(class: Class, ty: Ty) -> TypeErrorKind {
+    match class {
+        Class::Num => TypeErrorKind::NotANumber(ty),
+        Class::Eq | Class::Ord => TypeErrorKind::NotComparable(ty),
+        Class::Concat => TypeErrorKind::NotConcatenable(ty),
+    }
+}
+
+#[test]
+fn test_check_type_decl() {
+    let mut checker = TypeChecker::new();
+
+    let ast = "hoge ::= Int\nhoge := 3 * 4\nhoge + 1"
+        .parse::<Ast>()
+        .unwrap();
+
+    assert_eq!(checker.check(&ast), Ok(Ty::Int));
+}
+
+#[test]
+fn test_check_mismatch() {
+    use crate::lexer::Loc;
+    let mut checker = TypeChecker::new();
+
+    let ast = "f ::= Int -> Int\nf := 1 + 2".parse::<Ast>().unwrap();
+
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::Mismatch {
+                expected: Ty::fun(Ty::Int, Ty::Int),
+                found: Ty::Int,
+                expected_at: Some(Loc(6, 16)),
+            },
+            Loc(22, 27)
+        ))
+    );
+}
+
+#[test]
+fn test_check_unknown_type() {
+    use crate::lexer::Loc;
+    let mut checker = TypeChecker::new();
+
+    let ast = "x ::= [Boolean]".parse::<Ast>().unwrap();
+
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::UnknownType("Boolean".to_string()),
+            Loc(7, 14)
+        ))
+    );
+}
+
+#[test]
+fn test_check_lambda_against_decl() {
+    use crate::lexer::Loc;
+    let mut checker = TypeChecker::new();
+
+    let ast = "f ::= Int Int -> Int\nf := x n => x - n\nf 9 3"
+        .parse::<Ast>()
+        .unwrap();
+    assert_eq!(checker.check(&ast), Ok(Ty::Int));
+
+    let ast = "g ::= Int -> Int -> Int\ng := x => n => f"
+        .parse::<Ast>()
+        .unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::Mismatch {
+                expected: Ty::Int,
+                found: Ty::fun(Ty::Int, Ty::fun(Ty::Int, Ty::Int)),
+                expected_at: Some(Loc(6, 23)),
+            },
+            Loc(39, 40)
+        ))
+    );
+}
+
+#[test]
+fn test_check_not_a_function() {
+    use crate::lexer::Loc;
+    let mut checker = TypeChecker::new();
+
+    let ast = "x := 1\nx 2".parse::<Ast>().unwrap();
+
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::NotAFunction(Ty::Int),
+            Loc(7, 8)
+        ))
+    );
+}
+
+#[test]
+fn test_check_mixed_arithmetic() {
+    use crate::lexer::Loc;
+    let mut checker = TypeChecker::new();
+
+    let ast = "x ::= Float\nx := 1 + 2 * 0.5\ny ::= Int\ny := 7 / 2"
+        .parse::<Ast>()
+        .unwrap();
+    assert_eq!(checker.check(&ast), Ok(Ty::Void));
+
+    // the declaration of `y` was written in another source
+    checker.new_source();
+    let ast = "y := 1 + 0.5".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::Mismatch {
+                expected: Ty::Int,
+                found: Ty::Float,
+                expected_at: None,
+            },
+            Loc(5, 12)
+        ))
+    );
+
+    let ast = "f := x => x + 1\n1 + f".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::NotANumber(Ty::fun(Ty::Int, Ty::Int)),
+            Loc(20, 21)
+        ))
+    );
+
+    // `x` is not yet known to be an Int, so it is taken to be a Float
+    let ast = "g := x => x + 1.0\ng 2".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::Mismatch {
+                expected: Ty::Float,
+                found: Ty::Int,
+                expected_at: Some(Loc(18, 19)),
+            },
+            Loc(20, 21)
+        ))
+    );
+}
+
+#[test]
+fn test_check_string() {
+    use crate::lexer::Loc;
+    let mut checker = TypeChecker::new();
+
+    let ast = "s ::= String\ns := \"n = \" ++ String 42"
+        .parse::<Ast>()
+        .unwrap();
+    assert_eq!(checker.check(&ast), Ok(Ty::Void));
+
+    let ast = "\"n = \" ++ 42".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::Mismatch {
+                expected: Ty::String,
+                found: Ty::Int,
+                expected_at: Some(Loc(0, 6)),
+            },
+            Loc(10, 12)
+        ))
+    );
+
+    let ast = "s + 1".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::NotANumber(Ty::String),
+            Loc(0, 1)
+        ))
+    );
+}
+
+#[test]
+fn test_check_match() {
+    use crate::lexer::Loc;
+    let mut checker = TypeChecker::new();
+
+    let ast = "f ::= Int -> String\nf := n => match n | 0 => \"zero\" | m => String m"
+        .parse::<Ast>()
+        .unwrap();
+    assert_eq!(checker.check(&ast), Ok(Ty::Void));
+
+    let ast = "match 1 | 0 => 1 | _ => \"one\"".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::Mismatch {
+                expected: Ty::Int,
+                found: Ty::String,
+                expected_at: Some(Loc(15, 16)),
+            },
+            Loc(24, 29)
+        ))
+    );
+
+    let ast = "match f 1 | 0 => 1".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::Mismatch {
+                expected: Ty::String,
+                found: Ty::Int,
+                expected_at: Some(Loc(6, 9)),
+            },
+            Loc(12, 13)
+        ))
+    );
+}
+
+#[test]
+fn test_check_bool() {
+    use crate::lexer::Loc;
+    let mut checker = TypeChecker::new();
+
+    let ast = "p ::= Int -> Bool\np := n => n % 2 == 0 && !(n < 0.5) || n != 7\nif p 4 then \"a\" else \"b\""
+        .parse::<Ast>()
+        .unwrap();
+    assert_eq!(checker.check(&ast), Ok(Ty::String));
+
+    let ast = "if 1 then 2 else 3".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::Mismatch {
+                expected: Ty::Bool,
+                found: Ty::Int,
+                expected_at: None,
+            },
+            Loc(3, 4)
+        ))
+    );
+
+    let ast = "\"a\" == 1".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::Mismatch {
+                expected: Ty::String,
+                found: Ty::Int,
+                expected_at: Some(Loc(0, 3)),
+            },
+            Loc(7, 8)
+        ))
+    );
+
+    let ast = "True < False".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::NotComparable(Ty::Bool),
+            Loc(0, 12)
+        ))
+    );
+}
+
+#[test]
+fn test_check_block() {
+    use crate::lexer::Loc;
+    let mut checker = TypeChecker::new();
+
+    let src = "f ::= Int -> String
+f := n =>
+    s ::= String
+    s := String n
+    s ++ \"!\"
+s";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::UnboundVariable("s".to_string()),
+            Loc(78, 79)
+        ))
+    );
+}
+
+#[test]
+fn test_check_fn() {
+    use crate::lexer::Loc;
+    let mut checker = TypeChecker::new();
+
+    let src = "fn fact n := Int -> Int
+    if n == 0 then 1 else n * fact (n - 1)
+fn loop n => loop n
+fact 5";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(checker.check(&ast), Ok(Ty::Int));
+
+    // recursive calls are checked against the signature
+    let ast = "fn g n := Int -> Int => g \"a\"".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::Mismatch {
+                expected: Ty::Int,
+                found: Ty::String,
+                expected_at: Some(Loc(24, 25)),
+            },
+            Loc(26, 29)
+        ))
+    );
+}
+
+#[test]
+fn test_check_list() {
+    use crate::lexer::Loc;
+    let mut checker = TypeChecker::new();
+
+    let src = "xs := [1, 2]
+ys := 0 :: xs ++ []
+match ys
+    [] => 0
+    y :: rest => y + rest[0]";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(checker.check(&ast), Ok(Ty::Int));
+
+    let ast = "[[1], []] == [[2]]".parse::<Ast>().unwrap();
+    assert_eq!(checker.check(&ast), Ok(Ty::Bool));
+
+    let ast = "True :: xs".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::Mismatch {
+                expected: Ty::list(Ty::Bool),
+                found: Ty::list(Ty::Int),
+                expected_at: Some(Loc(0, 4)),
+            },
+            Loc(8, 10)
+        ))
+    );
+
+    let ast = "5[0]".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(TypeErrorKind::NotAList(Ty::Int), Loc(0, 1)))
+    );
+
+    let ast = "[1] < [2]".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::NotComparable(Ty::list(Ty::Int)),
+            Loc(0, 9)
+        ))
+    );
+
+    let ast = "match xs\n    [\"a\"] => 1".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::Mismatch {
+                expected: Ty::Int,
+                found: Ty::String,
+                expected_at: Some(Loc(6, 8)),
+            },
+            Loc(14, 17)
+        ))
+    );
+}
+
+#[test]
+fn test_check_type_def() {
+    use crate::lexer::Loc;
+    let mut checker = TypeChecker::new();
+
+    let src = "type Id = Int
+type A = MkA Id | NoA
+type B = MkB Int
+x ::= Id
+x := 3
+f ::= A -> Int
+f := a => match a
+    MkA n => n + x
+    NoA => 0
+f (MkA 1)";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(checker.check(&ast), Ok(Ty::Int));
+
+    // sum types are distinct even when their constructors look alike
+    let ast = "f (MkB 1)".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::Mismatch {
+                expected: Ty::Data("A".to_string()),
+                found: Ty::Data("B".to_string()),
+                expected_at: Some(Loc(0, 1)),
+            },
+            Loc(3, 8)
+        ))
+    );
+
+    let ast = "match MkA 1 | MkA => 0".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::WrongArity {
+                ctor: "MkA".to_string(),
+                expected: 1,
+                found: 0
+            },
+            Loc(14, 17)
+        ))
+    );
+
+    let ast = "match MkA 1 | MkC => 0".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::UnknownConstructor("MkC".to_string()),
+            Loc(14, 17)
+        ))
+    );
+
+    let ast = "type A = MkC String".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::DuplicateType("A".to_string()),
+            Loc(0, 19)
+        ))
+    );
+    let ast = "type Int = Float".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::DuplicateType("Int".to_string()),
+            Loc(0, 16)
+        ))
+    );
+    let ast = "type C = MkC | MkA String".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::DuplicateConstructor("MkA".to_string()),
+            Loc(0, 25)
+        ))
+    );
+    let ast = "type D = MkD | MkD".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::DuplicateConstructor("MkD".to_string()),
+            Loc(0, 18)
+        ))
+    );
+
+    // a rejected definition leaves no trace
+    let ast = "type C = MkC | MkE\nMkC".parse::<Ast>().unwrap();
+    assert_eq!(checker.check(&ast), Ok(Ty::Data("C".to_string())));
+
+    // values whose fields hold functions cannot be compared
+    let src = "type Nat = Z | S Nat
+type Op = Fun (Int -> Int) | Nop
+type Box = MkBox Op
+S Z == Z";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(checker.check(&ast), Ok(Ty::Bool));
+    for (src, ty) in &[("Nop == Nop", "Op"), ("MkBox Nop == MkBox Nop", "Box")] {
+        let ast = src.parse::<Ast>().unwrap();
+        assert_eq!(
+            checker.check(&ast),
+            Err(TypeError::new(
+                TypeErrorKind::NotComparable(Ty::Data(ty.to_string())),
+                Loc(0, src.len())
+            ))
+        );
+    }
+}
+
+#[test]
+fn test_check_infer() {
+    use crate::lexer::Loc;
+    let mut checker = TypeChecker::new();
+
+    let src = "id := x => x
+fn twice f x => f (f x)
+fn sum xs => match xs
+    [] => 0
+    x :: rest => x + sum rest
+fn less a b => a < b
+twice id (sum [1, 2])";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(checker.check(&ast), Ok(Ty::Int));
+    let scheme = |checker: &TypeChecker, var| checker.scheme_of(var).unwrap().to_string();
+    assert_eq!(scheme(&checker, "id"), "a -> a");
+    assert_eq!(scheme(&checker, "twice"), "(a -> a) -> a -> a");
+    assert_eq!(scheme(&checker, "sum"), "[Int] -> Int");
+    assert_eq!(scheme(&checker, "less"), "Ord a => a -> a -> Bool");
+
+    // each use of a binding instantiates its type afresh
+    let ast = "id \"a\" ++ String (id 1)".parse::<Ast>().unwrap();
+    assert_eq!(checker.check(&ast), Ok(Ty::String));
+
+    let ast = "less True False".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::NotComparable(Ty::Bool),
+            Loc(5, 9)
+        ))
+    );
+
+    // a lambda parameter has one type in the whole body
+    let ast = "f := x => x x".parse::<Ast>().unwrap();
+    assert!(matches!(
+        checker.check(&ast),
+        Err(TypeError {
+            value: TypeErrorKind::InfiniteType(..),
+            loc: Loc(10, 11),
+        })
+    ));
+
+    let ast = "f := x => if x then x else 1".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::Mismatch {
+                expected: Ty::Bool,
+                found: Ty::Int,
+                expected_at: Some(Loc(20, 21)),
+            },
+            Loc(27, 28)
+        ))
+    );
+}
+
+#[test]
+fn test_check_generic() {
+    use crate::lexer::Loc;
+    let mut checker = TypeChecker::new();
+
+    let src = "map ::= (a -> b) -> [a] -> [b]
+map := f => xs => match xs
+    [] => []
+    x :: rest => f x :: map f rest
+fn length xs := [a] -> Int
+    match xs
+        [] => 0
+        _ :: rest => 1 + length rest
+length (map String [1, 2]) + length [True]";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(checker.check(&ast), Ok(Ty::Int));
+    let map = checker.scheme_of("map").unwrap().to_string();
+    assert_eq!(map, "(a -> b) -> [a] -> [b]");
+
+    // the body must work for every type the variables may stand for
+    let ast = "fn first x y := a -> b -> a => y".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::Mismatch {
+                expected: Ty::Param("a".to_string()),
+                found: Ty::Param("b".to_string()),
+                expected_at: Some(Loc(16, 27)),
+            },
+            Loc(31, 32)
+        ))
+    );
+
+    let ast = "fn less x y := a -> a -> Bool => x < y"
+        .parse::<Ast>()
+        .unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::NotComparable(Ty::Param("a".to_string())),
+            Loc(33, 38)
+        ))
+    );
+    // a signature cannot carry the class, but it is inferred without one
+    let mut fresh = TypeChecker::new();
+    let ast = "fn less x y => x < y".parse::<Ast>().unwrap();
+    assert_eq!(fresh.check(&ast), Ok(Ty::Void));
+    let less = fresh.scheme_of("less").unwrap().to_string();
+    assert_eq!(less, "Ord a => a -> a -> Bool");
+
+    let ast = "type T = [a]".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::UnknownType("a".to_string()),
+            Loc(10, 11)
+        ))
+    );
+
+    // variables in messages are named by their order, not their ids
+    let ast = "map 1".parse::<Ast>().unwrap();
+    let err = checker.check(&ast).unwrap_err();
+    assert_eq!(err.to_string(), "type mismatch: expected a -> b, found Int");
+}
+
+#[test]
+fn test_check_coverage() {
+    use crate::lexer::Loc;
+    let mut checker = TypeChecker::new();
+
+    let src = "type Shape = Circle Int | Rect Int Int | Dot
+fn area s := Shape -> Int
+    match s
+        Circle r => r
+        Rect 1 h => h
+        Dot => 0
+match 3
+    1 => 1
+    n => n
+    2 => 2";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(checker.check(&ast), Ok(Ty::Int));
+    assert_eq!(
+        checker.take_warnings(),
+        vec![
+            Warning::new(
+                WarningKind::NonExhaustive("Rect _ _".to_string()),
+                Loc(75, 82)
+            ),
+            Warning::new(WarningKind::UnreachableArm, Loc(178, 184)),
+        ]
+    );
+    assert_eq!(checker.take_warnings(), vec![]);
+
+    let src = "fn f xs := [Bool] -> Int
+    match xs
+        [] => 0
+        True :: _ => 1
+        [False] => 2
+        [_, x] => 3";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(checker.check(&ast), Ok(Ty::Void));
+    assert_eq!(
+        checker.take_warnings(),
+        vec![Warning::new(
+            WarningKind::NonExhaustive("False :: _ :: _ :: _".to_string()),
+            Loc(29, 37)
+        )]
+    );
+
+    // a match covering every case raises no warnings
+    let src = "match [Dot]
+    [] => 0
+    Dot :: _ => 1
+    Circle _ :: _ => 2
+    Rect _ _ :: rest => 3";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(checker.check(&ast), Ok(Ty::Int));
+    assert_eq!(checker.take_warnings(), vec![]);
+}
+
+#[test]
+fn test_check_guard() {
+    use crate::lexer::Loc;
+    let mut checker = TypeChecker::new();
+
+    let src = "fn sign n := Int -> Int
+    match n
+        x if x < 0 => 0 - 1
+        x if x > 0 => 1";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(checker.check(&ast), Ok(Ty::Void));
+    // a guarded arm may not be taken, so it covers nothing
+    assert_eq!(
+        checker.take_warnings(),
+        vec![Warning::new(
+            WarningKind::NonExhaustive("_".to_string()),
+            Loc(28, 35)
+        )]
+    );
+
+    let ast = "match 1 | _ => 0 | x if x > 0 => x".parse::<Ast>().unwrap();
+    assert_eq!(checker.check(&ast), Ok(Ty::Int));
+    assert_eq!(
+        checker.take_warnings(),
+        vec![Warning::new(WarningKind::UnreachableArm, Loc(19, 34))]
+    );
+
+    let ast = "match 1 | x if x => x".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::Mismatch {
+                expected: Ty::Bool,
+                found: Ty::Int,
+                expected_at: None,
+            },
+            Loc(15, 16)
+        ))
+    );
+}
+
+#[test]
+fn test_check_tuple() {
+    use crate::lexer::Loc;
+    let mut checker = TypeChecker::new();
+
+    let src = "fn divmod a b => (a / b, a % b)
+(q, r) := divmod 9 4
+fn swap (a, b) => (b, a)
+swap (q, \"r\")";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Ok(Ty::Tuple(vec![Ty::String, Ty::Int]))
+    );
+    assert_eq!(checker.scheme_of("r").unwrap().to_string(), "Int");
+    assert_eq!(
+        checker.scheme_of("swap").unwrap().to_string(),
+        "(a, b) -> (b, a)"
+    );
+
+    let ast = "match (1, True) | (_, True) => 0".parse::<Ast>().unwrap();
+    assert_eq!(checker.check(&ast), Ok(Ty::Int));
+    assert_eq!(
+        checker.take_warnings(),
+        vec![Warning::new(
+            WarningKind::NonExhaustive("(_, False)".to_string()),
+            Loc(0, 15)
+        )]
+    );
+
+    // the pattern and the value must have the same size
+    let ast = "(a, b, c) := (1, 2)".parse::<Ast>().unwrap();
+    let err = checker.check(&ast).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "type mismatch: expected (Int, Int), found (a, b, c)"
+    );
+    assert_eq!(err.loc, Loc(0, 9));
+
+    let ast = "p ::= (Int, String)\np := (1, 2)".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::Mismatch {
+                expected: Ty::Tuple(vec![Ty::Int, Ty::String]),
+                found: Ty::Tuple(vec![Ty::Int, Ty::Int]),
+                expected_at: Some(Loc(6, 19)),
+            },
+            Loc(25, 31)
+        ))
+    );
+}
+
+#[test]
+fn test_check_duplicate_variable() {
+    use crate::lexer::Loc;
+    let mut checker = TypeChecker::new();
+
+    let ast = "type Pair = P Int Int".parse::<Ast>().unwrap();
+    assert_eq!(checker.check(&ast), Ok(Ty::Void));
+
+    // the error is at the second occurrence
+    for (src, loc) in &[
+        ("(x, x) := (1, \"a\")", Loc(4, 5)),
+        ("fn f (x, x) => x", Loc(9, 10)),
+        ("match P 1 2 | P x x => x", Loc(18, 19)),
+        (
+            "match { a = 1, b = 2 } | { a = x, b = x } => x",
+            Loc(38, 39),
+        ),
+        ("match [1, 2] | [x, x] => x", Loc(19, 20)),
+        ("match [1] | x :: x => x", Loc(17, 18)),
+    ] {
+        let ast = src.parse::<Ast>().unwrap();
+        assert_eq!(
+            checker.check(&ast),
+            Err(TypeError::new(
+                TypeErrorKind::DuplicateVariable("x".to_string()),
+                loc.clone()
+            ))
+        );
+    }
+}
+
+#[test]
+fn test_check_record() {
+    use crate::lexer::Loc;
+    let mut checker = TypeChecker::new();
+
+    let src = "type Person = { name : String, age : Int }
+fn birthday p := Person -> Person => { p | age = p.age + 1 }
+fn describe p := Person -> String
+    match p
+        { age = 0, name } => name ++ \" is new\"
+        { name } => name
+describe (birthday { name = \"a\", age = 0 })";
+    let ast = src.parse::<Ast>().unwrap();
+    assert_eq!(checker.check(&ast), Ok(Ty::String));
+    assert_eq!(checker.take_warnings(), vec![]);
+
+    let ast = "r := { a = 1 }\nr.b".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::NoField {
+                field: "b".to_string(),
+                ty: Ty::record(vec![("a".to_string(), Ty::Int)]),
+            },
+            Loc(15, 18)
+        ))
+    );
+
+    // a field can only be taken from a record whose type is known
+    let ast = "fn name p => p.name".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::UnknownRecord("name".to_string()),
+            Loc(13, 19)
+        ))
+    );
+
+    let ast = "{ a = 1, a = 2 }".parse::<Ast>().unwrap();
+    assert_eq!(
+        checker.check(&ast),
+        Err(TypeError::new(
+            TypeErrorKind::DuplicateField("a".to_string()),
+            Loc(0, 16)
+        ))
+    );
+}
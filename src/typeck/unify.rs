@@ -0,0 +1,295 @@
+use crate::typeck::{Class, Scheme, Ty};
+use std::collections::{HashMap, HashSet};
+
+/// Why two types could not be unified.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnifyError {
+    // the types have different shapes
+    Mismatch,
+    // the variable occurs in the type it would be bound to
+    Infinite(usize, Ty),
+    // the type is not a member of a class the variable is restricted to
+    Class(Class, Ty),
+}
+
+/// The solutions found so far for the type variables.
+#[derive(Debug, Default)]
+pub struct Unifier {
+    // what each variable has been bound to, indexed by variable
+    subst: Vec<Option<Ty>>,
+    // the classes each unbound variable is restricted to
+    classes: HashMap<usize, Vec<Class>>,
+    // the sum types with a field `==` cannot compare, such as a function
+    incomparable: HashSet<String>,
+}
+
+impl Unifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps the values of the sum type `name` out of `Eq`.
+    pub fn incomparable(&mut self, name: &str) {
+        self.incomparable.insert(name.to_string());
+    }
+
+    pub fn fresh(&mut self) -> Ty {
+        self.subst.push(None);
+        Ty::Var(self.subst.len() - 1)
+    }
+
+    // follows bound variables until the outermost constructor is known
+    fn shallow(&self, ty: &Ty) -> Ty {
+        let mut ty = ty.clone();
+        while let Ty::Var(v) = ty {
+            match self.subst[v] {
+                Some(ref bound) => ty = bound.clone(),
+                None => break,
+            }
+        }
+        ty
+    }
+
+    /// `ty` with every bound variable replaced by what it is bound to.
+    pub fn resolve(&self, ty: &Ty) -> Ty {
+        match self.shallow(ty) {
+            Ty::Fun(arg, ret) => Ty::fun(self.resolve(&arg), self.resolve(&ret)),
+            Ty::List(elem) => Ty::list(self.resolve(&elem)),
+            Ty::Tuple(elems) => Ty::Tuple(elems.iter().map(|elem| self.resolve(elem)).collect()),
+            Ty::Record(fields) => Ty::Record(
+                fields
+                    .iter()
+                    .map(|(name, ty)| (name.clone(), self.resolve(ty)))
+                    .collect(),
+            ),
+            ty => ty,
+        }
+    }
+
+    pub fn unify(&mut self, a: &Ty, b: &Ty) -> Result<(), UnifyError> {
+        match (self.shallow(a), self.shallow(b)) {
+            (Ty::Var(x), Ty::Var(y)) if x == y => Ok(()),
+            (Ty::Var(x), ty) | (ty, Ty::Var(x)) => self.bind(x, ty),
+            (Ty::Fun(a1, r1), Ty::Fun(a2, r2)) => {
+                self.unify(&a1, &a2)?;
+                self.unify(&r1, &r2)
+            }
+            (Ty::List(a), Ty::List(b)) => self.unify(&a, &b),
+            (Ty::Tuple(a), Ty::Tuple(b)) if a.len() == b.len() => {
+                a.iter().zip(&b).try_for_each(|(a, b)| self.unify(a, b))
+            }
+            // the fields are sorted, so records with the same fields list
+            // them in the same order
+            (Ty::Record(a), Ty::Record(b))
+                if a.len() == b.len() && a.iter().zip(&b).all(|((x, _), (y, _))| x == y) =>
+            {
+                a.iter()
+                    .zip(&b)
+                    .try_for_each(|((_, a), (_, b))| self.unify(a, b))
+            }
+            (a, b) if a == b => Ok(()),
+            _ => Err(UnifyError::Mismatch),
+        }
+    }
+
+    fn bind(&mut self, var: usize, ty: Ty) -> Result<(), UnifyError> {
+        let ty = self.resolve(&ty);
+        if ty.vars().contains(&var) {
+            return Err(UnifyError::Infinite(var, ty));
+        }
+        self.subst[var] = Some(ty.clone());
+        // the type takes over the restrictions of the variable
+        for class in self.classes.remove(&var).unwrap_or_default() {
+            if !self.constrain(&ty, class) {
+                return Err(UnifyError::Class(class, ty));
+            }
+        }
+        Ok(())
+    }
+
+    /// Restricts `ty` to the members of `class`, returning whether it can be
+    /// one.
+    pub fn constrain(&mut self, ty: &Ty, class: Class) -> bool {
+        use crate::typeck::Class::*;
+        match (class, self.shallow(ty)) {
+            (_, Ty::Var(v)) => {
+                let classes = self.classes.entry(v).or_default();
+                if !classes.contains(&class) {
+                    classes.push(class);
+                }
+                true
+            }
+            (Num, Ty::Int) | (Num, Ty::Float) => true,
+            (Ord, Ty::Int) | (Ord, Ty::Float) | (Ord, Ty::String) => true,
+            (Concat, Ty::String) | (Concat, Ty::List(_)) => true,
+            // a signature variable may be any type, even one without `==`
+            (_, Ty::Param(_)) => false,
+            // lists are equal when their elements are
+            (Eq, Ty::List(elem)) => self.constrain(&elem, Eq),
+            (Eq, Ty::Tuple(elems)) => elems.iter().all(|elem| self.constrain(elem, Eq)),
+            (Eq, Ty::Record(fields)) => fields.iter().all(|(_, ty)| self.constrain(ty, Eq)),
+            (Eq, Ty::Fun(..)) | (Eq, Ty::Void) => false,
+            (Eq, Ty::Data(name)) => !self.incomparable.contains(&name),
+            (Eq, _) => true,
+            _ => false,
+        }
+    }
+
+    /// Quantifies the variables of `ty` that do not occur in `env`, so that
+    /// each use of a binding can pick its own types for them.
+    pub fn generalize<'a>(&self, ty: &Ty, env: impl Iterator<Item = &'a Scheme>) -> Scheme {
+        let ty = self.resolve(ty);
+        let mut fixed = Vec::new();
+        for scheme in env {
+            let bound: Vec<_> = scheme.vars.iter().map(|(v, _)| *v).collect();
+            fixed.extend(
+                self.resolve(&scheme.ty)
+                    .vars()
+                    .into_iter()
+                    .filter(|v| !bound.contains(v)),
+            );
+        }
+        let vars = ty
+            .vars()
+            .into_iter()
+            .filter(|v| !fixed.contains(v))
+            .map(|v| (v, self.classes.get(&v).cloned().unwrap_or_default()))
+            .collect();
+        Scheme { vars, ty }
+    }
+
+    /// A copy of the type of `scheme` with fresh variables for the
+    /// quantified ones.
+    pub fn instantiate(&mut self, scheme: &Scheme) -> Ty {
+        let mut fresh = HashMap::new();
+        for (var, classes) in &scheme.vars {
+            let ty = self.fresh();
+            for &class in classes {
+                self.constrain(&ty, class);
+            }
+            fresh.insert(*var, ty);
+        }
+        substitute(&scheme.ty, &fresh)
+    }
+
+    /// The scheme of a declared type, quantifying its signature variables.
+    pub fn parametric(&mut self, ty: &Ty) -> Scheme {
+        let mut params = Vec::new();
+        let ty = self.replace_params(ty, &mut params);
+        let vars = params.into_iter().map(|(_, v)| (v, Vec::new())).collect();
+        Scheme { vars, ty }
+    }
+
+    // `ty` with a fresh variable for each signature variable, recording
+    // which
+    fn replace_params(&mut self, ty: &Ty, params: &mut Vec<(String, usize)>) -> Ty {
+        match ty {
+            Ty::Param(name) => match params.iter().find(|(param, _)| param == name) {
+                Some(&(_, v)) => Ty::Var(v),
+                None => {
+                    params.push((name.clone(), self.subst.len()));
+                    self.fresh()
+                }
+            },
+            Ty::Fun(arg, ret) => Ty::fun(
+                self.replace_params(arg, params),
+                self.replace_params(ret, params),
+            ),
+            Ty::List(elem) => Ty::list(self.replace_params(elem, params)),
+            Ty::Tuple(elems) => Ty::Tuple(
+                elems
+                    .iter()
+                    .map(|elem| self.replace_params(elem, params))
+                    .collect(),
+            ),
+            Ty::Record(fields) => Ty::Record(
+                fields
+                    .iter()
+                    .map(|(name, ty)| (name.clone(), self.replace_params(ty, params)))
+                    .collect(),
+            ),
+            ty => ty.clone(),
+        }
+    }
+}
+
+fn substitute(ty: &Ty, fresh: &HashMap<usize, Ty>) -> Ty {
+    match ty {
+        Ty::Var(v) => fresh.get(v).cloned().unwrap_or_else(|| ty.clone()),
+        Ty::Fun(arg, ret) => Ty::fun(substitute(arg, fresh), substitute(ret, fresh)),
+        Ty::List(elem) => Ty::list(substitute(elem, fresh)),
+        Ty::Tuple(elems) => Ty::Tuple(elems.iter().map(|elem| substitute(elem, fresh)).collect()),
+        Ty::Record(fields) => Ty::Record(
+            fields
+                .iter()
+                .map(|(name, ty)| (name.clone(), substitute(ty, fresh)))
+                .collect(),
+        ),
+        ty => ty.clone(),
+    }
+}
+
+#[test]
+fn test_unify() {
+    let mut u = Unifier::new();
+    let a = u.fresh();
+    let b = u.fresh();
+    u.unify(
+        &Ty::fun(a.clone(), Ty::Int),
+        &Ty::fun(Ty::list(b.clone()), b.clone()),
+    )
+    .unwrap();
+    assert_eq!(u.resolve(&a), Ty::list(Ty::Int));
+    assert_eq!(u.unify(&a, &Ty::Int), Err(UnifyError::Mismatch));
+
+    let c = u.fresh();
+    assert_eq!(
+        u.unify(&c, &Ty::list(c.clone())),
+        Err(UnifyError::Infinite(2, Ty::list(Ty::Var(2))))
+    );
+
+    let d = u.fresh();
+    assert!(u.constrain(&d, Class::Num));
+    assert_eq!(
+        u.unify(&Ty::String, &d),
+        Err(UnifyError::Class(Class::Num, Ty::String))
+    );
+    assert!(u.constrain(&Ty::list(Ty::Int), Class::Eq));
+    assert!(!u.constrain(&Ty::list(Ty::fun(Ty::Int, Ty::Int)), Class::Eq));
+
+    // tuples of different sizes never unify
+    let e = u.fresh();
+    assert_eq!(
+        u.unify(
+            &Ty::Tuple(vec![Ty::Int, e.clone()]),
+            &Ty::Tuple(vec![Ty::Int, Ty::Bool])
+        ),
+        Ok(())
+    );
+    assert_eq!(u.resolve(&e), Ty::Bool);
+    assert_eq!(
+        u.unify(
+            &Ty::Tuple(vec![Ty::Int, Ty::Int]),
+            &Ty::Tuple(vec![Ty::Int])
+        ),
+        Err(UnifyError::Mismatch)
+    );
+}
+
+#[test]
+fn test_generalize() {
+    let mut u = Unifier::new();
+    let a = u.fresh();
+    let b = u.fresh();
+    u.constrain(&b, Class::Ord);
+    let env = [Scheme::mono(a.clone())];
+    let scheme = u.generalize(&Ty::fun(a.clone(), b.clone()), env.iter());
+    assert_eq!(scheme.vars, vec![(1, vec![Class::Ord])]);
+    let ty = u.instantiate(&scheme);
+    assert_eq!(ty, Ty::fun(a, Ty::Var(2)));
+    assert!(!u.constrain(&Ty::Bool, Class::Ord));
+    assert_eq!(
+        u.unify(&Ty::Var(2), &Ty::Bool),
+        Err(UnifyError::Class(Class::Ord, Ty::Bool))
+    );
+}
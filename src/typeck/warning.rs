@@ -0,0 +1,31 @@
+use crate::error::{print_annot, SourceMap};
+use crate::util::Annot;
+
+/// A likely mistake that does not stop the program from running.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WarningKind {
+    // a `match` that no arm of matches the values of this pattern
+    NonExhaustive(String),
+    // an arm matching only values that earlier arms do
+    UnreachableArm,
+}
+
+pub type Warning = Annot<WarningKind>;
+
+use std::fmt;
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::WarningKind::*;
+        match self.value {
+            NonExhaustive(ref pat) => write!(f, "match is not exhaustive: {} is not covered", pat),
+            UnreachableArm => write!(f, "unreachable arm: earlier arms match all its values"),
+        }
+    }
+}
+
+impl Warning {
+    pub fn show_diagnostic(&self, src: &SourceMap) {
+        eprintln!("warning: {}", self);
+        print_annot(src, self.loc.clone());
+    }
+}
@@ -0,0 +1,196 @@
+use num_bigint::BigInt;
+use num_traits::{Num, Pow, Signed, ToPrimitive, Zero};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// An integer of unbounded size. Values that fit in an `i64` are kept unboxed
+/// and computed with machine arithmetic; only results that overflow are
+/// promoted to a `BigInt`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Integer {
+    Small(i64),
+    // never holds a value that fits in `Small`, so the derived `PartialEq` holds
+    Big(BigInt),
+}
+
+use self::Integer::*;
+
+macro_rules! arith {
+    ($name:ident, $checked:ident, $op:tt) => {
+        pub fn $name(&self, rhs: &Integer) -> Integer {
+            if let (Small(a), Small(b)) = (self, rhs) {
+                if let Some(n) = a.$checked(*b) {
+                    return Small(n);
+                }
+            }
+            Integer::from(self.to_big() $op rhs.to_big())
+        }
+    };
+}
+
+impl Integer {
+    fn to_big(&self) -> BigInt {
+        match self {
+            Small(n) => BigInt::from(*n),
+            Big(n) => n.clone(),
+        }
+    }
+
+    pub fn from_str_radix(s: &str, radix: u32) -> Option<Integer> {
+        match i64::from_str_radix(s, radix) {
+            Ok(n) => Some(Small(n)),
+            Err(_) => BigInt::from_str_radix(s, radix).ok().map(Integer::from),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Small(n) => *n == 0,
+            Big(n) => n.is_zero(),
+        }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        match self {
+            Small(n) => *n < 0,
+            Big(n) => n.is_negative(),
+        }
+    }
+
+    pub fn is_even(&self) -> bool {
+        match self {
+            Small(n) => n % 2 == 0,
+            Big(n) => (n % 2u32).is_zero(),
+        }
+    }
+
+    // the number of bits in the magnitude, 0 for zero
+    pub fn bits(&self) -> u64 {
+        match self {
+            Small(n) => u64::from(64 - n.unsigned_abs().leading_zeros()),
+            Big(n) => n.bits() as u64,
+        }
+    }
+
+    pub fn to_u32(&self) -> Option<u32> {
+        match self {
+            Small(n) => n.to_u32(),
+            Big(_) => None,
+        }
+    }
+
+    pub fn to_usize(&self) -> Option<usize> {
+        match self {
+            Small(n) => n.to_usize(),
+            Big(_) => None,
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Small(n) => *n as f64,
+            Big(n) => n.to_f64().unwrap_or(f64::NAN),
+        }
+    }
+
+    pub fn neg(&self) -> Integer {
+        match self {
+            Small(n) => match n.checked_neg() {
+                Some(n) => Small(n),
+                None => Integer::from(-BigInt::from(*n)),
+            },
+            Big(n) => Integer::from(-n),
+        }
+    }
+
+    arith!(add, checked_add, +);
+    arith!(sub, checked_sub, -);
+    arith!(mul, checked_mul, *);
+    // division and remainder truncate toward zero; `rhs` must not be zero
+    arith!(div, checked_div, /);
+    arith!(rem, checked_rem, %);
+
+    pub fn pow(&self, exp: u32) -> Integer {
+        if let Small(n) = self {
+            if let Some(n) = n.checked_pow(exp) {
+                return Small(n);
+            }
+        }
+        Integer::from(Pow::pow(&self.to_big(), exp))
+    }
+}
+
+impl From<BigInt> for Integer {
+    fn from(n: BigInt) -> Self {
+        match n.to_i64() {
+            Some(n) => Small(n),
+            None => Big(n),
+        }
+    }
+}
+
+impl From<i64> for Integer {
+    fn from(n: i64) -> Self {
+        Small(n)
+    }
+}
+
+impl From<i32> for Integer {
+    fn from(n: i32) -> Self {
+        Small(n.into())
+    }
+}
+
+impl PartialEq<i64> for Integer {
+    fn eq(&self, other: &i64) -> bool {
+        match self {
+            Small(n) => n == other,
+            Big(_) => false,
+        }
+    }
+}
+
+impl PartialOrd for Integer {
+    fn partial_cmp(&self, other: &Integer) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Integer {
+    fn cmp(&self, other: &Integer) -> Ordering {
+        match (self, other) {
+            (Small(a), Small(b)) => a.cmp(b),
+            (a, b) => a.to_big().cmp(&b.to_big()),
+        }
+    }
+}
+
+impl fmt::Display for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Small(n) => n.fmt(f),
+            Big(n) => n.fmt(f),
+        }
+    }
+}
+
+#[test]
+fn test_promote_and_demote() {
+    let max = Integer::from(i64::MAX);
+    let big = max.add(&Integer::from(1));
+    assert!(matches!(big, Big(_)));
+    assert_eq!(big.to_string(), "9223372036854775808");
+    assert_eq!(big.sub(&Integer::from(1)), Small(i64::MAX));
+    assert_eq!(
+        Integer::from(i64::MIN).neg().to_string(),
+        "9223372036854775808"
+    );
+    assert_eq!(
+        Integer::from(2).pow(100).to_string(),
+        "1267650600228229401496703205376"
+    );
+    assert_eq!(
+        Integer::from_str_radix("99999999999999999999", 10).map(|n| n.rem(&Integer::from(7))),
+        Some(Integer::from(1))
+    );
+}
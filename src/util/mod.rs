@@ -1,3 +1,5 @@
 mod annot;
+mod int;
 
-pub use annot::*;
\ No newline at end of file
+pub use annot::*;
+pub use int::*;
\ No newline at end of file